@@ -1,15 +1,448 @@
-use crate::config::Config;
-use clap::Command;
+use crate::cli::map::MapCommand;
+use crate::cli::output::OutputFormat;
+use crate::config::{Config, PathfindingConfig};
+use crate::error::NovaError;
+use crate::logging;
+use crate::simulation::entities::{MapConstants, ResourcePlacement, Symmetry};
+use crate::simulation::map_file::MapLimits;
+use crate::simulation::pathfinding::{MovementMode, PathfindingAlgorithm};
+use crate::simulation::setup::RobotPlacement;
+use crate::simulation::view::Rect;
+use crate::visualizer::theme::Palette;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use std::fmt;
+use std::path::PathBuf;
 
-pub fn parse_args() -> Option<Config> {
-    let matches = Command::new("nova")
+pub enum Action {
+    Simulate(Config),
+    Map(MapCommand),
+}
+
+pub fn parse_args() -> Result<Option<Action>, NovaError> {
+    let defaults = Config::defaults(Vec::new());
+    let matches = command(&defaults).get_matches();
+
+    logging::init(matches.get_one::<PathBuf>("log-file").map(PathBuf::as_path))?;
+
+    let palette = matches
+        .get_one::<Palette>("palette")
+        .copied()
+        .unwrap_or_default();
+
+    let (sub, config) = match matches.subcommand() {
+        Some(("start", start)) => {
+            let placements = start
+                .get_many::<RobotPlacement>("robot")
+                .map(|placements| placements.cloned().collect())
+                .unwrap_or_default();
+            let base = Config::defaults(placements);
+            let constants = map_constants(start, &base);
+            let world = Config {
+                seed: start.get_one::<u64>("seed").copied().unwrap_or(base.seed),
+                map_width: start
+                    .get_one::<usize>("width")
+                    .copied()
+                    .unwrap_or(base.map_width),
+                map_height: start
+                    .get_one::<usize>("height")
+                    .copied()
+                    .unwrap_or(base.map_height),
+                robots_count: start
+                    .get_one::<usize>("robots")
+                    .copied()
+                    .unwrap_or(base.robots_count),
+                obstacle_density: constants.obstacle_density,
+                symmetry: constants.symmetry,
+                resource_abundance: constants.resource_abundance,
+                resource_placement: constants.resource_placement,
+                erosion_iterations: constants.erosion_iterations,
+                smoothing_iterations: constants.smoothing_iterations,
+                ..base
+            };
+            let config = if start.get_flag("headless") {
+                world
+            } else {
+                Config::new(world)?
+            };
+            let config = Config {
+                min_score: start.get_one::<f64>("min-score").copied(),
+                no_go_zones: no_go_zones(start),
+                ..config
+            };
+            (start, config)
+        }
+        Some(("daily", daily)) => (daily, Config::daily(!daily.get_flag("headless"))),
+        Some(("map", map)) => return Ok(Some(Action::Map(map_command(map, palette, &defaults)))),
+        _ => return Ok(None),
+    };
+
+    let pathfinding = PathfindingConfig {
+        algorithm: sub
+            .get_one::<PathfindingAlgorithm>("pathfinding")
+            .copied()
+            .unwrap_or(defaults.pathfinding.algorithm),
+        heuristic_weight: sub
+            .get_one::<f64>("heuristic-weight")
+            .copied()
+            .unwrap_or(defaults.pathfinding.heuristic_weight),
+        expansion_budget: sub.get_one::<usize>("expansion-budget").copied(),
+        movement: sub
+            .get_one::<MovementMode>("movement")
+            .copied()
+            .unwrap_or(defaults.pathfinding.movement),
+    };
+
+    Ok(Some(Action::Simulate(Config {
+        palette,
+        pathfinding,
+        profile: sub.get_flag("profile"),
+        profile_trace: sub.get_one::<PathBuf>("profile-trace").cloned(),
+        mem_stats: sub.get_flag("mem-stats"),
+        headless: sub.get_flag("headless"),
+        output: sub
+            .get_one::<OutputFormat>("output")
+            .copied()
+            .unwrap_or(defaults.output),
+        ..config
+    })))
+}
+
+fn command(defaults: &Config) -> Command {
+    Command::new("nova")
         .about("Robot Swarm Simulation")
         .version("0.1.0")
-        .subcommand(Command::new("start").about("Start the simulation"))
-        .get_matches();
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("PATH")
+                .help("Write structured logs to a rotating file")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("palette")
+                .long("palette")
+                .value_name("PALETTE")
+                .help("Color palette for the map view")
+                .value_parser(value_parser!(Palette))
+                .default_value("default")
+                .global(true),
+        )
+        .subcommand(
+            Command::new("start")
+                .about("Start the simulation")
+                .arg(
+                    Arg::new("robot")
+                        .long("robot")
+                        .value_name("TYPE@X,Y")
+                        .help("Place a robot at an exact position instead of around the station")
+                        .value_parser(value_parser!(RobotPlacement))
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("min-score")
+                        .long("min-score")
+                        .value_name("SCORE")
+                        .help(
+                            "Reroll the world with derived seeds until its interest score \
+                             reaches SCORE",
+                        )
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("robots")
+                        .long("robots")
+                        .value_name("COUNT")
+                        .help(with_default(
+                            "Robots spawned around the station",
+                            defaults.robots_count,
+                        ))
+                        .value_parser(value_parser!(usize))
+                        .conflicts_with("robot"),
+                )
+                .arg(no_go_arg())
+                .args(generation_args(defaults))
+                .args(simulation_args(defaults)),
+        )
+        // Nothing that changes the world is accepted for daily, so everyone
+        // plays the same one.
+        .subcommand(
+            Command::new("daily")
+                .about("Play today's shared daily world")
+                .args(simulation_args(defaults)),
+        )
+        .subcommand(
+            Command::new("map")
+                .about("Generate and inspect maps without running the simulation")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("generate")
+                        .about("Generate a map and save it to a file")
+                        .arg(map_file_arg())
+                        .arg(no_go_arg())
+                        .args(generation_args(defaults)),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Draw a saved map")
+                        .arg(map_file_arg())
+                        .arg(max_size_arg()),
+                )
+                .subcommand(
+                    Command::new("stats")
+                        .about("Print statistics for a saved map")
+                        .arg(map_file_arg())
+                        .arg(max_size_arg()),
+                ),
+        )
+}
+
+// Help text ending in the default a missing flag falls back to. Defaults are
+// taken from the config types rather than set on the flags, so the two cannot
+// drift apart.
+fn with_default(help: &str, default: impl fmt::Display) -> String {
+    format!("{} [default: {}]", help, default)
+}
+
+fn simulation_args(defaults: &Config) -> [Arg; 9] {
+    let pathfinding = &defaults.pathfinding;
+    [
+        Arg::new("pathfinding")
+            .long("pathfinding")
+            .value_name("ALGORITHM")
+            .help(with_default(
+                "Pathfinding algorithm used by robots",
+                pathfinding.algorithm,
+            ))
+            .value_parser(value_parser!(PathfindingAlgorithm)),
+        Arg::new("heuristic-weight")
+            .long("heuristic-weight")
+            .value_name("WEIGHT")
+            .help(with_default(
+                "Inflate the A* heuristic to trade path optimality for speed",
+                pathfinding.heuristic_weight,
+            ))
+            .value_parser(value_parser!(f64)),
+        Arg::new("expansion-budget")
+            .long("expansion-budget")
+            .value_name("NODES")
+            .help("Stop A* after this many expansions and return a partial path")
+            .value_parser(value_parser!(usize)),
+        Arg::new("movement")
+            .long("movement")
+            .value_name("MODE")
+            .help(with_default(
+                "Whether robots may step diagonally",
+                pathfinding.movement,
+            ))
+            .value_parser(value_parser!(MovementMode)),
+        Arg::new("profile")
+            .long("profile")
+            .help("Print a per-phase timing breakdown at exit")
+            .action(ArgAction::SetTrue),
+        Arg::new("profile-trace")
+            .long("profile-trace")
+            .value_name("PATH")
+            .help("Also write the timings as a chrome-tracing JSON file")
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new("mem-stats")
+            .long("mem-stats")
+            .help("Report estimated memory use at startup and at exit")
+            .action(ArgAction::SetTrue),
+        Arg::new("headless")
+            .long("headless")
+            .help("Skip the map view and print end-of-run statistics only")
+            .action(ArgAction::SetTrue),
+        Arg::new("output")
+            .long("output")
+            .value_name("FORMAT")
+            .help(with_default(
+                "Format of the headless statistics",
+                defaults.output,
+            ))
+            .value_parser(value_parser!(OutputFormat))
+            .requires("headless"),
+    ]
+}
+
+fn no_go_arg() -> Arg {
+    Arg::new("no-go")
+        .long("no-go")
+        .value_name("X,Y,WxH")
+        .help("Mark a rectangle robots must never enter")
+        .value_parser(value_parser!(Rect))
+        .action(ArgAction::Append)
+}
+
+fn no_go_zones(matches: &ArgMatches) -> Vec<Rect> {
+    matches
+        .get_many::<Rect>("no-go")
+        .map(|zones| zones.copied().collect())
+        .unwrap_or_default()
+}
+
+fn map_file_arg() -> Arg {
+    Arg::new("file")
+        .value_name("FILE")
+        .help("Map file")
+        .value_parser(value_parser!(PathBuf))
+        .required(true)
+}
+
+fn max_size_arg() -> Arg {
+    let limits = MapLimits::default();
+    Arg::new("max-size")
+        .long("max-size")
+        .value_name("WxH")
+        .help(with_default(
+            "Refuse map files declaring a larger size",
+            format!("{}x{}", limits.max_width, limits.max_height),
+        ))
+        .value_parser(value_parser!(MapLimits))
+}
+
+fn generation_args(defaults: &Config) -> [Arg; 9] {
+    [
+        Arg::new("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help(with_default("World seed", defaults.seed))
+            .value_parser(value_parser!(u64)),
+        Arg::new("width")
+            .long("width")
+            .value_name("CELLS")
+            .help(with_default("Map width", defaults.map_width))
+            .value_parser(value_parser!(usize)),
+        Arg::new("height")
+            .long("height")
+            .value_name("CELLS")
+            .help(with_default("Map height", defaults.map_height))
+            .value_parser(value_parser!(usize)),
+        Arg::new("obstacle-density")
+            .long("obstacle-density")
+            .value_name("DENSITY")
+            .help(with_default(
+                "How much of the map is mountains, hills and canyons, 0.0..=1.0",
+                defaults.obstacle_density,
+            ))
+            .value_parser(value_parser!(f64)),
+        Arg::new("symmetry")
+            .long("symmetry")
+            .value_name("SYMMETRY")
+            .help(with_default(
+                "Make both halves of the map match",
+                defaults.symmetry,
+            ))
+            .value_parser(value_parser!(Symmetry)),
+        Arg::new("resource-abundance")
+            .long("resource-abundance")
+            .value_name("ABUNDANCE")
+            .help(with_default(
+                "Scale the number and size of deposits",
+                defaults.resource_abundance,
+            ))
+            .value_parser(value_parser!(f64)),
+        Arg::new("resource-placement")
+            .long("resource-placement")
+            .value_name("PLACEMENT")
+            .help(with_default(
+                "How deposits are scattered",
+                defaults.resource_placement,
+            ))
+            .value_parser(value_parser!(ResourcePlacement)),
+        Arg::new("erosion")
+            .long("erosion")
+            .value_name("ITERATIONS")
+            .help(with_default(
+                "Erosion passes that carve valleys into the terrain",
+                defaults.erosion_iterations,
+            ))
+            .value_parser(value_parser!(usize)),
+        Arg::new("smoothing")
+            .long("smoothing")
+            .value_name("ITERATIONS")
+            .help(with_default(
+                "Smoothing passes that remove isolated terrain cells",
+                defaults.smoothing_iterations,
+            ))
+            .value_parser(value_parser!(usize)),
+    ]
+}
+
+fn map_command(matches: &ArgMatches, palette: Palette, defaults: &Config) -> MapCommand {
+    let (name, sub) = matches.subcommand().expect("map requires a subcommand");
+    let path = sub
+        .get_one::<PathBuf>("file")
+        .cloned()
+        .expect("file is required");
+    let limits = || {
+        sub.get_one::<MapLimits>("max-size")
+            .copied()
+            .unwrap_or_default()
+    };
+
+    match name {
+        "generate" => MapCommand::Generate {
+            path,
+            seed: sub.get_one::<u64>("seed").copied().unwrap_or(defaults.seed),
+            width: sub
+                .get_one::<usize>("width")
+                .copied()
+                .unwrap_or(defaults.map_width),
+            height: sub
+                .get_one::<usize>("height")
+                .copied()
+                .unwrap_or(defaults.map_height),
+            constants: map_constants(sub, defaults),
+            no_go_zones: no_go_zones(sub),
+        },
+        "show" => MapCommand::Show {
+            path,
+            palette,
+            limits: limits(),
+        },
+        _ => MapCommand::Stats {
+            path,
+            limits: limits(),
+        },
+    }
+}
+
+fn map_constants(matches: &ArgMatches, defaults: &Config) -> MapConstants {
+    MapConstants {
+        obstacle_density: matches
+            .get_one::<f64>("obstacle-density")
+            .copied()
+            .unwrap_or(defaults.obstacle_density),
+        symmetry: matches
+            .get_one::<Symmetry>("symmetry")
+            .copied()
+            .unwrap_or(defaults.symmetry),
+        resource_abundance: matches
+            .get_one::<f64>("resource-abundance")
+            .copied()
+            .unwrap_or(defaults.resource_abundance),
+        resource_placement: matches
+            .get_one::<ResourcePlacement>("resource-placement")
+            .copied()
+            .unwrap_or(defaults.resource_placement),
+        erosion_iterations: matches
+            .get_one::<usize>("erosion")
+            .copied()
+            .unwrap_or(defaults.erosion_iterations),
+        smoothing_iterations: matches
+            .get_one::<usize>("smoothing")
+            .copied()
+            .unwrap_or(defaults.smoothing_iterations),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    match matches.subcommand() {
-        Some(("start", _)) => Some(Config::new()),
-        _ => None,
+    #[test]
+    fn the_command_line_is_consistent() {
+        command(&Config::defaults(Vec::new())).debug_assert();
     }
 }
@@ -0,0 +1,114 @@
+use crate::cli::progress::GenerationProgress;
+use crate::error::NovaError;
+use crate::simulation::entities::{Map, MapConstants, ResourceType, TerrainType};
+use crate::simulation::map_file::MapLimits;
+use crate::simulation::view::Rect;
+use crate::visualizer::theme::Palette;
+use crate::visualizer::{MAX_RENDER_HEIGHT, MAX_RENDER_WIDTH, render_map};
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum MapCommand {
+    Generate {
+        path: PathBuf,
+        seed: u64,
+        width: usize,
+        height: usize,
+        constants: MapConstants,
+        no_go_zones: Vec<Rect>,
+    },
+    Show {
+        path: PathBuf,
+        palette: Palette,
+        limits: MapLimits,
+    },
+    Stats {
+        path: PathBuf,
+        limits: MapLimits,
+    },
+}
+
+pub fn run(command: MapCommand) -> Result<(), NovaError> {
+    match command {
+        MapCommand::Generate {
+            path,
+            seed,
+            width,
+            height,
+            constants,
+            no_go_zones,
+        } => {
+            let mut progress = GenerationProgress::new();
+            let map = Map::with_progress(width, height, seed, &constants, |phase, done, total| {
+                progress.update(phase, done, total)
+            });
+            progress.finish();
+
+            let mut map = map?;
+            for zone in no_go_zones {
+                map.add_no_go_zone(zone)?;
+            }
+            map.save_to_file(&path)?;
+            log::info!("saved map seed={} to {}", seed, path.display());
+            println!(
+                "🗺️  Saved {}x{} map (seed {}) to {}",
+                width,
+                height,
+                seed,
+                path.display()
+            );
+        }
+        MapCommand::Show {
+            path,
+            palette,
+            limits,
+        } => {
+            let map = Map::load_from_file_with(&path, limits)?;
+            let center = (map.width / 2, map.height / 2);
+            let rect = Rect::centered(&map, center, MAX_RENDER_WIDTH, MAX_RENDER_HEIGHT);
+            if (rect.width, rect.height) != (map.width, map.height) {
+                println!(
+                    "Showing {}x{} of {}x{} from ({}, {})",
+                    rect.width, rect.height, map.width, map.height, rect.x, rect.y
+                );
+            }
+            print!(
+                "{}",
+                render_map(&map.view(rect), None, &[], &palette.theme())
+            );
+        }
+        MapCommand::Stats { path, limits } => {
+            let map = Map::load_from_file_with(&path, limits)?;
+            println!("Map {} ({}x{}):", path.display(), map.width, map.height);
+            println!("  Seed: {}", map.seed());
+            println!("  Interest score: {:.2}", map.interest_score());
+            print_stats(&map);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_stats(map: &Map) {
+    let stats = map.stats();
+    println!("  Terrain:");
+    for terrain in [
+        TerrainType::Plain,
+        TerrainType::Hill,
+        TerrainType::Canyon,
+        TerrainType::Mountain,
+    ] {
+        println!("    {:?}: {}", terrain, stats.terrain_count(terrain));
+    }
+    println!("  Resources:");
+    for resource_type in ResourceType::value_variants() {
+        let resource = stats.resource(resource_type);
+        println!(
+            "    {}: {} deposits, {} units",
+            resource_type, resource.deposits, resource.total
+        );
+    }
+    println!("  Deep scan deposits: {}", map.deep_resources.len());
+    println!("  Discovered: {}/{} cells", stats.discovered, stats.cells);
+}
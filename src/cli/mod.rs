@@ -1 +1,4 @@
 pub mod args;
+pub mod map;
+pub mod output;
+pub mod progress;
@@ -0,0 +1,184 @@
+use crate::simulation::entities::{ResourceType, RobotId, TerrainType};
+use crate::simulation::pathfinding::Path;
+use crate::simulation::pathfinding::distance_field::DistanceField;
+use crate::simulation::world::World;
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[OutputFormat::Json, OutputFormat::Csv]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            OutputFormat::Json => PossibleValue::new("json"),
+            OutputFormat::Csv => PossibleValue::new("csv"),
+        })
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+// End-of-run statistics as flat, ordered columns so the JSON object and the
+// CSV row always carry the same fields. Missing values are null in JSON and
+// empty in CSV.
+pub struct RunReport {
+    columns: Vec<(String, Option<String>)>,
+}
+
+impl RunReport {
+    pub fn new(world: &World, home: &DistanceField, routes: &[(RobotId, Path)]) -> RunReport {
+        let map = world.map();
+        let stats = map.stats();
+        let mut report = RunReport {
+            columns: Vec::new(),
+        };
+
+        report.push("seed", map.seed());
+        report.push("width", map.width);
+        report.push("height", map.height);
+        report.push("robots", world.robots().len());
+        report.push("ticks", world.tick());
+        report.push("station_x", world.station().x);
+        report.push("station_y", world.station().y);
+        report.push("interest_score", format!("{:.4}", map.interest_score()));
+        for terrain in [
+            TerrainType::Plain,
+            TerrainType::Hill,
+            TerrainType::Canyon,
+            TerrainType::Mountain,
+        ] {
+            let name = format!("{:?}", terrain).to_lowercase();
+            report.push(&format!("terrain_{}", name), stats.terrain_count(terrain));
+        }
+        for resource_type in ResourceType::value_variants() {
+            let resource = stats.resource(resource_type);
+            let name = resource_type.name().replace('-', "_");
+            report.push(&format!("{}_deposits", name), resource.deposits);
+            report.push(&format!("{}_units", name), resource.total);
+        }
+        report.push("deep_deposits", map.deep_resources.len());
+        report.push("discovered", stats.discovered);
+        report.push("cells", stats.cells);
+        report.push(
+            "robot_energy",
+            world
+                .robots()
+                .iter()
+                .map(|robot| u64::from(robot.energy))
+                .sum::<u64>(),
+        );
+        report.columns.push((
+            "farthest_return".to_string(),
+            home.max_cost().map(|cost| cost.to_string()),
+        ));
+        report.push("routes_planned", routes.len());
+        report.push(
+            "routes_partial",
+            routes.iter().filter(|(_, path)| !path.complete).count(),
+        );
+        report.push(
+            "routes_cost",
+            routes
+                .iter()
+                .map(|(_, path)| u64::from(path.cost))
+                .sum::<u64>(),
+        );
+
+        report
+    }
+
+    fn push(&mut self, name: &str, value: impl ToString) {
+        self.columns
+            .push((name.to_string(), Some(value.to_string())));
+    }
+
+    pub fn write(&self, format: OutputFormat, out: &mut impl Write) -> io::Result<()> {
+        match format {
+            OutputFormat::Json => {
+                let fields: Vec<String> = self
+                    .columns
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("\"{}\":{}", name, value.as_deref().unwrap_or("null"))
+                    })
+                    .collect();
+                writeln!(out, "{{{}}}", fields.join(","))
+            }
+            OutputFormat::Csv => {
+                let names: Vec<&str> = self.columns.iter().map(|(name, _)| name.as_str()).collect();
+                let values: Vec<&str> = self
+                    .columns
+                    .iter()
+                    .map(|(_, value)| value.as_deref().unwrap_or(""))
+                    .collect();
+                writeln!(out, "{}", names.join(","))?;
+                writeln!(out, "{}", values.join(","))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::entities::Map;
+
+    fn written(report: &RunReport, format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        report.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_and_csv_carry_the_same_columns() {
+        let world = World::new(Map::new(16, 16, 42).unwrap(), 3, &[]).unwrap();
+        let map = world.map();
+        // A target off the map leaves every cell unreached, so there is no
+        // farthest return to report.
+        let home = DistanceField::new(map, (map.width, map.height));
+        let report = RunReport::new(&world, &home, &[]);
+
+        let json = written(&report, OutputFormat::Json);
+        let fields: Vec<(&str, &str)> = json
+            .trim()
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .map(|field| field.split_once(':').unwrap())
+            .collect();
+        let csv = written(&report, OutputFormat::Csv);
+        let mut lines = csv.lines();
+        let names: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let values: Vec<&str> = lines.next().unwrap().split(',').collect();
+        assert_eq!(lines.next(), None);
+
+        assert_eq!(names.len(), values.len());
+        assert_eq!(fields.len(), names.len());
+        for (((json_name, json_value), name), value) in fields.iter().zip(&names).zip(&values) {
+            assert_eq!(json_name.trim_matches('"'), *name);
+            if *name == "farthest_return" {
+                assert_eq!(*json_value, "null");
+                assert_eq!(*value, "");
+            } else {
+                assert_eq!(json_value, value, "{}", name);
+            }
+        }
+        assert!(names.contains(&"farthest_return"));
+    }
+}
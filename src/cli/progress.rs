@@ -0,0 +1,33 @@
+use crate::simulation::entities::GenerationPhase;
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub struct GenerationProgress {
+    bar: ProgressBar,
+    phase: Option<GenerationPhase>,
+}
+
+impl GenerationProgress {
+    pub fn new() -> GenerationProgress {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::with_template("{msg:>12} [{bar:40}] {pos}/{len}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+
+        GenerationProgress { bar, phase: None }
+    }
+
+    pub fn update(&mut self, phase: GenerationPhase, done: usize, total: usize) {
+        if self.phase != Some(phase) {
+            self.phase = Some(phase);
+            self.bar.set_length(total as u64);
+            self.bar.set_message(phase.to_string());
+        }
+        self.bar.set_position(done as u64);
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -1,4 +1,18 @@
+use crate::cli::output::OutputFormat;
+use crate::simulation::entities::{MAX_MAP_CELLS, MapConstants, ResourcePlacement, Symmetry};
+use crate::simulation::pathfinding::{MovementMode, PathfindingAlgorithm};
+use crate::simulation::setup::RobotPlacement;
+use crate::simulation::view::Rect;
+use crate::visualizer::theme::Palette;
+use std::fmt;
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAILY_MAP_WIDTH: usize = 64;
+const DAILY_MAP_HEIGHT: usize = 32;
+const DAILY_ROBOTS_COUNT: usize = 8;
+const DAILY_OBSTACLE_DENSITY: f64 = 0.5;
 
 #[derive(Debug)]
 pub struct Config {
@@ -6,10 +20,119 @@ pub struct Config {
     pub map_width: usize,
     pub map_height: usize,
     pub robots_count: usize,
+    pub obstacle_density: f64,
+    pub symmetry: Symmetry,
+    pub resource_abundance: f64,
+    pub resource_placement: ResourcePlacement,
+    pub erosion_iterations: usize,
+    pub smoothing_iterations: usize,
+    pub robot_placements: Vec<RobotPlacement>,
+    pub palette: Palette,
+    pub pathfinding: PathfindingConfig,
+    pub profile: bool,
+    pub profile_trace: Option<PathBuf>,
+    pub mem_stats: bool,
+    pub min_score: Option<f64>,
+    pub no_go_zones: Vec<Rect>,
+    pub headless: bool,
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathfindingConfig {
+    pub algorithm: PathfindingAlgorithm,
+    pub heuristic_weight: f64,
+    pub expansion_budget: Option<usize>,
+    pub movement: MovementMode,
+}
+
+impl Default for PathfindingConfig {
+    fn default() -> Self {
+        PathfindingConfig {
+            algorithm: PathfindingAlgorithm::AStar,
+            heuristic_weight: 1.0,
+            expansion_budget: None,
+            movement: MovementMode::Cardinal,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    InvalidDimensions { width: usize, height: usize },
+    MapTooLarge { width: usize, height: usize },
+    NoRobots,
+    TooManyRobots { robots: usize, cells: usize },
+    InvalidObstacleDensity(f64),
+    InvalidResourceAbundance(f64),
+    InvalidHeuristicWeight(f64),
+    InvalidMinScore(f64),
+    UnsupportedMovement(PathfindingAlgorithm, MovementMode),
+    UnsupportedOption(PathfindingAlgorithm, &'static str),
 }
 
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::InvalidDimensions { width, height } => {
+                write!(
+                    f,
+                    "map dimensions must be at least 1x1, got {}x{}",
+                    width, height
+                )
+            }
+            ConfigError::MapTooLarge { width, height } => write!(
+                f,
+                "a {}x{} map exceeds the limit of {} cells",
+                width, height, MAX_MAP_CELLS
+            ),
+            ConfigError::NoRobots => write!(f, "at least one robot is required"),
+            ConfigError::TooManyRobots { robots, cells } => {
+                write!(
+                    f,
+                    "{} robots do not fit on a map of {} cells",
+                    robots, cells
+                )
+            }
+            ConfigError::InvalidObstacleDensity(density) => {
+                write!(
+                    f,
+                    "obstacle density must be within 0.0..=1.0, got {}",
+                    density
+                )
+            }
+            ConfigError::InvalidResourceAbundance(abundance) => {
+                write!(
+                    f,
+                    "resource abundance must be 0.0 or more, got {}",
+                    abundance
+                )
+            }
+            ConfigError::InvalidHeuristicWeight(weight) => {
+                write!(f, "heuristic weight must be 1.0 or more, got {}", weight)
+            }
+            ConfigError::InvalidMinScore(score) => {
+                write!(f, "minimum score must be within 0.0..=1.0, got {}", score)
+            }
+            ConfigError::UnsupportedMovement(algorithm, movement) => {
+                write!(
+                    f,
+                    "{} pathfinding does not support {} movement",
+                    algorithm, movement
+                )
+            }
+            ConfigError::UnsupportedOption(algorithm, flag) => {
+                write!(f, "{} pathfinding does not support --{}", algorithm, flag)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 impl Config {
-    pub fn new() -> Config {
+    // Prompts for each world setting, offering the value from `defaults`.
+    pub fn new(defaults: Config) -> io::Result<Config> {
         println!(
             r#"
   _   _  ____  __      __
@@ -30,49 +153,293 @@ impl Config {
 "#
         );
 
-        let seed: u64 = Self::prompt_with_default("Seed", 42);
-        let map_height: usize = Self::prompt_with_default("Map Height", 10);
-        let map_width: usize = Self::prompt_with_default("Map Width", 10);
-        let robots_count: usize = Self::prompt_with_default("Robots Count", 5);
+        let seed: u64 = Self::prompt_with_default("Seed", defaults.seed)?;
+        let map_height: usize = Self::prompt_with_default("Map Height", defaults.map_height)?;
+        let map_width: usize = Self::prompt_with_default("Map Width", defaults.map_width)?;
+        let robots_count: usize = if defaults.robot_placements.is_empty() {
+            Self::prompt_with_default("Robots Count", defaults.robots_count)?
+        } else {
+            defaults.robots_count
+        };
+        let obstacle_density: f64 =
+            Self::prompt_with_default("Obstacle Density", defaults.obstacle_density)?;
+        let symmetry: Symmetry = Self::prompt_with_default("Symmetry", defaults.symmetry)?;
+        let resource_abundance: f64 =
+            Self::prompt_with_default("Resource Abundance", defaults.resource_abundance)?;
+        let resource_placement: ResourcePlacement =
+            Self::prompt_with_default("Resource Placement", defaults.resource_placement)?;
+        let erosion_iterations: usize =
+            Self::prompt_with_default("Erosion Iterations", defaults.erosion_iterations)?;
+        let smoothing_iterations: usize =
+            Self::prompt_with_default("Smoothing Iterations", defaults.smoothing_iterations)?;
 
         println!("\n✅ Configuration complete. Launching simulation...\n");
 
-        Config {
+        Ok(Config {
+            seed,
             map_height,
             map_width,
             robots_count,
-            seed,
+            obstacle_density,
+            symmetry,
+            resource_abundance,
+            resource_placement,
+            erosion_iterations,
+            smoothing_iterations,
+            ..defaults
+        })
+    }
+
+    // The built-in world settings, used as-is when nobody is there to answer
+    // the prompts, as in headless runs.
+    pub fn defaults(robot_placements: Vec<RobotPlacement>) -> Config {
+        let robots_count = match robot_placements.len() {
+            0 => 5,
+            placed => placed,
+        };
+        let constants = MapConstants::default();
+
+        Config {
+            seed: 42,
+            map_height: 10,
+            map_width: 10,
+            robots_count,
+            obstacle_density: constants.obstacle_density,
+            symmetry: constants.symmetry,
+            resource_abundance: constants.resource_abundance,
+            resource_placement: constants.resource_placement,
+            erosion_iterations: constants.erosion_iterations,
+            smoothing_iterations: constants.smoothing_iterations,
+            robot_placements,
+            palette: Palette::default(),
+            pathfinding: PathfindingConfig::default(),
+            profile: false,
+            profile_trace: None,
+            mem_stats: false,
+            min_score: None,
+            no_go_zones: Vec::new(),
+            headless: false,
+            output: OutputFormat::default(),
+        }
+    }
+
+    pub fn daily(announce: bool) -> Config {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let (year, month, day) = civil_from_days(secs / 86_400);
+
+        if announce {
+            println!(
+                "🗓️  Daily world for {:04}-{:02}-{:02} (UTC)",
+                year, month, day
+            );
+        }
+
+        Config {
+            seed: year * 10_000 + month * 100 + day,
+            map_width: DAILY_MAP_WIDTH,
+            map_height: DAILY_MAP_HEIGHT,
+            robots_count: DAILY_ROBOTS_COUNT,
+            obstacle_density: DAILY_OBSTACLE_DENSITY,
+            ..Config::defaults(Vec::new())
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let (width, height) = (self.map_width, self.map_height);
+        if width == 0 || height == 0 {
+            return Err(ConfigError::InvalidDimensions { width, height });
+        }
+        let cells = width
+            .checked_mul(height)
+            .filter(|&cells| cells <= MAX_MAP_CELLS)
+            .ok_or(ConfigError::MapTooLarge { width, height })?;
+
+        if self.robots_count == 0 {
+            return Err(ConfigError::NoRobots);
+        }
+        if self.robots_count > cells {
+            return Err(ConfigError::TooManyRobots {
+                robots: self.robots_count,
+                cells,
+            });
+        }
+        if !(0.0..=1.0).contains(&self.obstacle_density) {
+            return Err(ConfigError::InvalidObstacleDensity(self.obstacle_density));
+        }
+        if !(self.resource_abundance.is_finite() && self.resource_abundance >= 0.0) {
+            return Err(ConfigError::InvalidResourceAbundance(
+                self.resource_abundance,
+            ));
+        }
+        let weight = self.pathfinding.heuristic_weight;
+        if !(weight.is_finite() && weight >= 1.0) {
+            return Err(ConfigError::InvalidHeuristicWeight(weight));
+        }
+        let PathfindingConfig {
+            algorithm,
+            movement,
+            expansion_budget,
+            ..
+        } = self.pathfinding;
+        if algorithm != PathfindingAlgorithm::AStar {
+            if movement != MovementMode::Cardinal {
+                return Err(ConfigError::UnsupportedMovement(algorithm, movement));
+            }
+            if weight > 1.0 {
+                return Err(ConfigError::UnsupportedOption(
+                    algorithm,
+                    "heuristic-weight",
+                ));
+            }
+            if expansion_budget.is_some() {
+                return Err(ConfigError::UnsupportedOption(
+                    algorithm,
+                    "expansion-budget",
+                ));
+            }
+        }
+        if let Some(score) = self.min_score
+            && !(0.0..=1.0).contains(&score)
+        {
+            return Err(ConfigError::InvalidMinScore(score));
         }
+
+        Ok(())
     }
 
-    pub fn prompt_with_default<T>(name: &str, default: T) -> T
+    pub fn prompt_with_default<T>(name: &str, default: T) -> io::Result<T>
     where
         T: std::str::FromStr + std::fmt::Display,
         <T as std::str::FromStr>::Err: std::fmt::Debug,
     {
         loop {
             print!("> {} [{}]: ", name, default);
-            io::stdout().flush().unwrap();
+            io::stdout().flush()?;
 
             let mut input = String::new();
             if io::stdin().read_line(&mut input).is_err() {
                 println!("⚠️ Error reading input. Using default.");
-                return default;
+                return Ok(default);
             }
 
             let trimmed = input.trim();
             if trimmed.is_empty() {
-                return default;
+                return Ok(default);
             }
 
             match trimmed.parse() {
-                Ok(val) => return val,
+                Ok(val) => return Ok(val),
                 Err(_) => {
                     println!(
-                        "❌ Invalid input. Please enter a valid number or press Enter for default."
+                        "❌ Invalid input. Please enter a valid value or press Enter for default."
                     );
                 }
             }
         }
     }
 }
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sized(map_width: usize, map_height: usize) -> Config {
+        Config {
+            map_width,
+            map_height,
+            ..Config::daily(false)
+        }
+    }
+
+    #[test]
+    fn rejects_zero_dimensions_before_counting_robots() {
+        assert!(matches!(
+            sized(0, 10).validate(),
+            Err(ConfigError::InvalidDimensions { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_dimensions_whose_product_overflows() {
+        assert!(matches!(
+            sized(99_999_999_999, 99_999_999_999).validate(),
+            Err(ConfigError::MapTooLarge { .. })
+        ));
+        assert!(matches!(
+            sized(usize::MAX, 2).validate(),
+            Err(ConfigError::MapTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn defaults_are_valid_and_count_placed_robots() {
+        let defaults = Config::defaults(Vec::new());
+        assert!(defaults.validate().is_ok());
+        assert_eq!(defaults.robots_count, 5);
+
+        let placements: Vec<RobotPlacement> = ["explorer@1,1", "harvester@2,2"]
+            .iter()
+            .map(|placement| placement.parse().unwrap())
+            .collect();
+        assert_eq!(Config::defaults(placements).robots_count, 2);
+    }
+
+    #[test]
+    fn search_tuning_is_only_accepted_for_astar() {
+        let with = |algorithm, heuristic_weight, expansion_budget| Config {
+            pathfinding: PathfindingConfig {
+                algorithm,
+                heuristic_weight,
+                expansion_budget,
+                movement: MovementMode::Cardinal,
+            },
+            ..Config::defaults(Vec::new())
+        };
+
+        assert!(
+            with(PathfindingAlgorithm::AStar, 5.0, Some(1))
+                .validate()
+                .is_ok()
+        );
+        for algorithm in [
+            PathfindingAlgorithm::Bidirectional,
+            PathfindingAlgorithm::Hierarchical,
+        ] {
+            assert!(with(algorithm, 1.0, None).validate().is_ok());
+            assert!(matches!(
+                with(algorithm, 5.0, None).validate(),
+                Err(ConfigError::UnsupportedOption(_, "heuristic-weight"))
+            ));
+            assert!(matches!(
+                with(algorithm, 1.0, Some(1)).validate(),
+                Err(ConfigError::UnsupportedOption(_, "expansion-budget"))
+            ));
+        }
+    }
+
+    #[test]
+    fn days_since_the_epoch_become_calendar_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(20_088), (2024, 12, 31));
+        assert_eq!(civil_from_days(20_741), (2026, 10, 15));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::config::ConfigError;
+use crate::simulation::entities::MapError;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum NovaError {
+    Config(ConfigError),
+    Map(MapError),
+    Io(io::Error),
+}
+
+impl NovaError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            NovaError::Config(_) => 2,
+            NovaError::Map(_) => 3,
+            NovaError::Io(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for NovaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NovaError::Config(e) => write!(f, "configuration error: {}", e),
+            NovaError::Map(e) => write!(f, "map error: {}", e),
+            NovaError::Io(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NovaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NovaError::Config(e) => Some(e),
+            NovaError::Map(e) => Some(e),
+            NovaError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<ConfigError> for NovaError {
+    fn from(e: ConfigError) -> Self {
+        NovaError::Config(e)
+    }
+}
+
+impl From<MapError> for NovaError {
+    fn from(e: MapError) -> Self {
+        NovaError::Map(e)
+    }
+}
+
+impl From<io::Error> for NovaError {
+    fn from(e: io::Error) -> Self {
+        NovaError::Io(e)
+    }
+}
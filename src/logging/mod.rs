@@ -0,0 +1,36 @@
+pub mod rotation;
+
+use env_logger::{Builder, Env, Target, WriteStyle};
+use rotation::RotatingFile;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const MAX_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub fn init(log_file: Option<&Path>) -> io::Result<()> {
+    let default_level = if log_file.is_some() { "info" } else { "warn" };
+    let mut builder = Builder::from_env(Env::default().default_filter_or(default_level));
+
+    if let Some(path) = log_file {
+        builder
+            .target(Target::Pipe(Box::new(RotatingFile::open(
+                path, MAX_BYTES, MAX_AGE,
+            )?)))
+            .write_style(WriteStyle::Never)
+            .format(|buf, record| {
+                writeln!(
+                    buf,
+                    "ts={} level={} target={} msg={:?}",
+                    buf.timestamp_millis(),
+                    record.level(),
+                    record.target(),
+                    record.args().to_string()
+                )
+            });
+    }
+
+    builder.init();
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const KEEP_FILES: usize = 5;
+
+// Appends to `path` and, once it holds `max_bytes` or is `max_age` old, moves
+// it to `path.1`, shifting older files up to `path.5` and dropping the rest.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    opened_at: SystemTime,
+    max_bytes: u64,
+    max_age: Duration,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_age: Duration) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        let opened_at = metadata.created().unwrap_or_else(|_| SystemTime::now());
+
+        Ok(RotatingFile {
+            path: path.to_path_buf(),
+            file,
+            written: metadata.len(),
+            opened_at,
+            max_bytes,
+            max_age,
+        })
+    }
+
+    fn should_rotate(&self) -> bool {
+        let age = self.opened_at.elapsed().unwrap_or_default();
+        self.written >= self.max_bytes || age >= self.max_age
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        for index in (1..KEEP_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        self.opened_at = SystemTime::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writing_past_the_size_limit_keeps_five_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("nova-rotation-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("nova.log");
+
+        let mut log = RotatingFile::open(&path, 16, Duration::from_secs(3600)).unwrap();
+        for line in 0..10 {
+            let line = format!("line {:02} of the log\n", line);
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        // Every write fills the file, so each one after the first rotates
+        // and the newest line is left alone in the live file.
+        let read = |path: &Path| fs::read_to_string(path).unwrap();
+        assert_eq!(read(&path), "line 09 of the log\n");
+        for index in 1..=KEEP_FILES {
+            let expected = format!("line {:02} of the log\n", 9 - index);
+            assert_eq!(read(&log.rotated_path(index)), expected);
+        }
+        assert!(!log.rotated_path(KEEP_FILES + 1).exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), KEEP_FILES + 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,20 +1,284 @@
 mod cli;
 mod config;
+mod error;
+mod logging;
+mod profiling;
 mod simulation;
+mod visualizer;
 
+use cli::args::Action;
+use cli::output::RunReport;
+use cli::progress::GenerationProgress;
 use config::Config;
+use error::NovaError;
+use profiling::Profiler;
+use simulation::entities::{DEEP_LAYER_STREAM, Map, MapConstants, MapError, derive_seed};
+use simulation::pathfinding::{self, distance_field::DistanceField};
+use simulation::world::World;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use visualizer::frontend::{Frame, Frontend, PlainTextFrontend};
 
-fn main() {
-    if let Some(cfg) = cli::args::parse_args() {
-        start_simulation(cfg);
+const MAX_REROLLS: u64 = 50;
+const _: () = assert!(MAX_REROLLS < DEEP_LAYER_STREAM);
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), NovaError> {
+    match cli::args::parse_args()? {
+        Some(Action::Simulate(cfg)) => start_simulation(cfg)?,
+        Some(Action::Map(command)) => cli::map::run(command)?,
+        None => println!("use cmd start to start"),
+    }
+
+    Ok(())
+}
+
+// Without a minimum score the first world is kept. Otherwise reroll with
+// derived seeds and fall back to the best world seen.
+fn generate_map(
+    config: &Config,
+    constants: &MapConstants,
+    progress: &mut GenerationProgress,
+) -> Result<Map, MapError> {
+    let mut best: Option<(f64, Map)> = None;
+
+    for attempt in 0..=MAX_REROLLS {
+        let seed = if attempt == 0 {
+            config.seed
+        } else {
+            derive_seed(config.seed, attempt)
+        };
+        let map = Map::with_progress(
+            config.map_width,
+            config.map_height,
+            seed,
+            constants,
+            |phase, done, total| progress.update(phase, done, total),
+        )?;
+
+        let Some(min_score) = config.min_score else {
+            return Ok(map);
+        };
+        let score = map.interest_score();
+        log::info!("generated world seed={} score={:.3}", seed, score);
+        if score >= min_score {
+            return Ok(map);
+        }
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, map));
+        }
+    }
+
+    Ok(best.expect("at least one world is generated").1)
+}
+
+fn start_simulation(config: Config) -> Result<(), NovaError> {
+    config.validate()?;
+    log::info!(
+        "starting simulation seed={} map={}x{} robots={}",
+        config.seed,
+        config.map_width,
+        config.map_height,
+        config.robots_count
+    );
+
+    if !config.headless {
+        print_config(&config);
+    }
+    // Headless stdout carries nothing but the report, so memory and profile
+    // output goes to stderr there.
+    let mut diagnostics: Box<dyn Write> = if config.headless {
+        Box::new(io::stderr())
     } else {
-        println!("use cmd start to start")
+        Box::new(io::stdout())
+    };
+
+    let constants = MapConstants {
+        obstacle_density: config.obstacle_density,
+        symmetry: config.symmetry,
+        resource_abundance: config.resource_abundance,
+        resource_placement: config.resource_placement,
+        erosion_iterations: config.erosion_iterations,
+        smoothing_iterations: config.smoothing_iterations,
+    };
+    let mut profiler = Profiler::new(config.profile || config.profile_trace.is_some());
+    let mut progress = GenerationProgress::new();
+    let map = profiler.scope("map generation", || {
+        generate_map(&config, &constants, &mut progress)
+    });
+    progress.finish();
+
+    let mut map = map?;
+    for &zone in &config.no_go_zones {
+        map.add_no_go_zone(zone)?;
+    }
+    log::info!("map generated deposits={}", map.stats().deposits());
+    if !config.headless {
+        if let Some(min_score) = config.min_score {
+            let score = map.interest_score();
+            if map.seed() != config.seed {
+                println!("  Rerolled to seed {}", map.seed());
+            }
+            if score < min_score {
+                println!(
+                    "⚠️  No world reached score {} in {} rerolls, keeping the best",
+                    min_score, MAX_REROLLS
+                );
+            }
+        }
+        println!("  Interest score: {:.2}", map.interest_score());
+        cli::map::print_stats(&map);
+    }
+    if config.mem_stats {
+        writeln!(diagnostics, "  Memory at startup (estimated):")?;
+        write!(diagnostics, "{}", map.memory_usage())?;
     }
+
+    let world = profiler.scope("setup", || {
+        World::new(map, config.robots_count, &config.robot_placements)
+    })?;
+    let (map, station) = (world.map(), world.station());
+    log::info!("station placed at ({}, {})", station.x, station.y);
+
+    // Shared by every robot deciding whether it can still make it home.
+    let home = profiler.scope("distance field", || {
+        DistanceField::with_movement(map, (station.x, station.y), config.pathfinding.movement)
+    });
+    let routes = profiler.scope("pathfinding", || {
+        let pathfinder = pathfinding::from_config(&config.pathfinding, map);
+        world.plan_routes(pathfinder.as_ref())
+    });
+
+    if config.headless {
+        RunReport::new(&world, &home, &routes).write(config.output, &mut io::stdout().lock())?;
+    } else {
+        println!("  Station: ({}, {})", station.x, station.y);
+        if let Some(max_cost) = home.max_cost() {
+            println!("  Farthest return trip: {} energy", max_cost);
+        }
+        let partial = routes.iter().filter(|(_, path)| !path.complete).count();
+        println!(
+            "  Initial routes: {} planned ({} partial), total cost {}",
+            routes.len(),
+            partial,
+            routes
+                .iter()
+                .map(|(_, path)| u64::from(path.cost))
+                .sum::<u64>()
+        );
+
+        let mut frontend = PlainTextFrontend::new(config.palette.theme());
+        frontend.init(map)?;
+        profiler.scope("rendering", || {
+            frontend.render(&Frame {
+                map,
+                station,
+                robots: world.robots(),
+            })
+        })?;
+    }
+
+    if config.mem_stats {
+        let mut usage = map.memory_usage();
+        usage.add("distance field", home.memory_bytes());
+        usage.add("robots", std::mem::size_of_val(world.robots()));
+        writeln!(diagnostics)?;
+        writeln!(diagnostics, "Memory at exit (estimated):")?;
+        write!(diagnostics, "{}", usage)?;
+    }
+    if config.profile || config.profile_trace.is_some() {
+        writeln!(diagnostics)?;
+        write!(diagnostics, "{}", profiler.report())?;
+    }
+    if let Some(path) = &config.profile_trace {
+        profiler.write_chrome_trace(path)?;
+        writeln!(diagnostics, "  Trace written to {}", path.display())?;
+    }
+
+    Ok(())
 }
 
-fn start_simulation(config: Config) {
+fn print_config(config: &Config) {
     println!("Starting simulation with:");
     println!("  Seed: {}", config.seed);
     println!("  Map: {}x{}", config.map_width, config.map_height);
     println!("  Robots: {}", config.robots_count);
+    println!("  Obstacle density: {}", config.obstacle_density);
+    println!("  Symmetry: {}", config.symmetry);
+    println!("  Resource abundance: {}", config.resource_abundance);
+    println!("  Resource placement: {}", config.resource_placement);
+    println!("  Erosion iterations: {}", config.erosion_iterations);
+    println!("  Smoothing iterations: {}", config.smoothing_iterations);
+    println!(
+        "  Pathfinding: {} (heuristic weight {}, {} movement)",
+        config.pathfinding.algorithm,
+        config.pathfinding.heuristic_weight,
+        config.pathfinding.movement
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small(seed: u64, min_score: Option<f64>) -> Config {
+        Config {
+            seed,
+            map_width: 16,
+            map_height: 16,
+            min_score,
+            ..Config::defaults(Vec::new())
+        }
+    }
+
+    fn generate(config: &Config) -> Map {
+        generate_map(
+            config,
+            &MapConstants::default(),
+            &mut GenerationProgress::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn the_first_world_is_kept_without_a_reachable_minimum() {
+        assert_eq!(generate(&small(7, None)).seed(), 7);
+        assert_eq!(generate(&small(7, Some(0.0))).seed(), 7);
+    }
+
+    #[test]
+    fn rerolls_stop_at_the_first_world_reaching_the_minimum() {
+        let scores: Vec<(u64, f64)> = (0..=MAX_REROLLS)
+            .map(|attempt| match attempt {
+                0 => 7,
+                attempt => derive_seed(7, attempt),
+            })
+            .map(|seed| (seed, generate(&small(seed, None)).interest_score()))
+            .collect();
+
+        let min_score = scores[0].1 + 0.01;
+        let map = generate(&small(7, Some(min_score)));
+        let (first, _) = scores
+            .iter()
+            .find(|&&(_, score)| score >= min_score)
+            .expect("some reroll beats the first world");
+        assert_eq!(map.seed(), *first);
+
+        // Nothing reaches a perfect score, so the best world seen is kept.
+        let map = generate(&small(7, Some(1.0)));
+        let best = scores.iter().map(|&(_, score)| score).fold(0.0, f64::max);
+        assert_eq!(map.interest_score(), best);
+    }
 }
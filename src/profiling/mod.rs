@@ -0,0 +1,99 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+struct Span {
+    name: &'static str,
+    start: Duration,
+    duration: Duration,
+}
+
+// Records how long each named scope takes. A disabled profiler still runs
+// the scoped work but skips the clock reads entirely.
+pub struct Profiler {
+    enabled: bool,
+    origin: Instant,
+    spans: Vec<Span>,
+}
+
+impl Profiler {
+    pub fn new(enabled: bool) -> Profiler {
+        Profiler {
+            enabled,
+            origin: Instant::now(),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn scope<T, F>(&mut self, name: &'static str, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        if !self.enabled {
+            return f();
+        }
+
+        let started = Instant::now();
+        let result = f();
+        self.spans.push(Span {
+            name,
+            start: started - self.origin,
+            duration: started.elapsed(),
+        });
+        result
+    }
+
+    pub fn report(&self) -> String {
+        let mut totals: Vec<(&'static str, Duration, usize)> = Vec::new();
+        let mut slots = HashMap::new();
+        for span in &self.spans {
+            let slot = *slots.entry(span.name).or_insert_with(|| {
+                totals.push((span.name, Duration::ZERO, 0));
+                totals.len() - 1
+            });
+            totals[slot].1 += span.duration;
+            totals[slot].2 += 1;
+        }
+        totals.sort_by_key(|&(_, total, _)| Reverse(total));
+
+        let overall: Duration = totals.iter().map(|(_, total, _)| *total).sum();
+        let mut out = String::from("Profile:\n");
+        for (name, total, calls) in totals {
+            let share = if overall.is_zero() {
+                0.0
+            } else {
+                total.as_secs_f64() / overall.as_secs_f64() * 100.0
+            };
+            out.push_str(&format!(
+                "  {:<16} {:>10.3} ms {:>5.1}% ({} calls)\n",
+                name,
+                total.as_secs_f64() * 1000.0,
+                share,
+                calls
+            ));
+        }
+        out
+    }
+
+    // Chrome's trace event format, viewable in chrome://tracing or Perfetto.
+    pub fn write_chrome_trace(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "[")?;
+        for (i, span) in self.spans.iter().enumerate() {
+            let separator = if i + 1 < self.spans.len() { "," } else { "" };
+            writeln!(
+                out,
+                r#"  {{"name": "{}", "ph": "X", "ts": {}, "dur": {}, "pid": 1, "tid": 1}}{}"#,
+                span.name,
+                span.start.as_micros(),
+                span.duration.as_micros(),
+                separator
+            )?;
+        }
+        writeln!(out, "]")?;
+        out.flush()
+    }
+}
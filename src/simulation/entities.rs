@@ -1,5 +1,11 @@
-use noise::Perlin;
-use std::collections::HashMap;
+use crate::simulation::view::Rect;
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
+use noise::{NoiseFn, Perlin};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -9,15 +15,915 @@ pub enum ResourceType {
     ScientificInterest,
 }
 
+impl ResourceType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceType::Energy => "energy",
+            ResourceType::Mineral => "mineral",
+            ResourceType::ScientificInterest => "scientific-interest",
+        }
+    }
+}
+
+impl ValueEnum for ResourceType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ResourceType::Energy,
+            ResourceType::Mineral,
+            ResourceType::ScientificInterest,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let value = PossibleValue::new(self.name());
+        Some(match self {
+            ResourceType::ScientificInterest => value.alias("science"),
+            _ => value,
+        })
+    }
+}
+
+impl FromStr for ResourceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| format!("unknown resource type '{}'", s))
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TerrainType {
+    Plain,
+    Hill,
+    Canyon,
+    Mountain,
+}
+
+impl TerrainType {
+    pub fn from_u8(value: u8) -> TerrainType {
+        match value {
+            0 => TerrainType::Plain,
+            1 => TerrainType::Hill,
+            2 => TerrainType::Canyon,
+            _ => TerrainType::Mountain,
+        }
+    }
+
+    pub fn is_traversable(&self) -> bool {
+        self.movement_cost().is_some()
+    }
+
+    pub fn movement_cost(&self) -> Option<u32> {
+        match self {
+            TerrainType::Plain => Some(1),
+            TerrainType::Hill => Some(2),
+            TerrainType::Canyon => Some(3),
+            TerrainType::Mountain => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationPhase {
+    Terrain,
+    Erosion,
+    Smoothing,
+    Resources,
+    Validation,
+    DeepScan,
+}
+
+impl fmt::Display for GenerationPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerationPhase::Terrain => write!(f, "terrain"),
+            GenerationPhase::Erosion => write!(f, "erosion"),
+            GenerationPhase::Smoothing => write!(f, "smoothing"),
+            GenerationPhase::Resources => write!(f, "resources"),
+            GenerationPhase::Validation => write!(f, "validation"),
+            GenerationPhase::DeepScan => write!(f, "deep scan"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    InvalidDimensions {
+        width: usize,
+        height: usize,
+    },
+    TooLarge {
+        width: usize,
+        height: usize,
+    },
+    InvalidObstacleDensity(f64),
+    InvalidResourceAbundance(f64),
+    NoTraversableTerrain,
+    NoStationSite,
+    NoSpawnRoom {
+        requested: usize,
+        available: usize,
+    },
+    InvalidRobotPlacement {
+        x: usize,
+        y: usize,
+        reason: &'static str,
+    },
+    ZoneOutsideMap {
+        zone: Rect,
+        width: usize,
+        height: usize,
+    },
+    Io(io::Error),
+    InvalidFile {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::InvalidDimensions { width, height } => {
+                write!(f, "invalid map dimensions {}x{}", width, height)
+            }
+            MapError::TooLarge { width, height } => write!(
+                f,
+                "a {}x{} map exceeds the limit of {} cells",
+                width, height, MAX_MAP_CELLS
+            ),
+            MapError::InvalidObstacleDensity(density) => {
+                write!(f, "obstacle density {} is outside 0.0..=1.0", density)
+            }
+            MapError::InvalidResourceAbundance(abundance) => {
+                write!(
+                    f,
+                    "resource abundance {} must be a non-negative number",
+                    abundance
+                )
+            }
+            MapError::NoTraversableTerrain => write!(f, "map has no traversable terrain"),
+            MapError::NoStationSite => write!(f, "no free plain cell to place the station on"),
+            MapError::NoSpawnRoom {
+                requested,
+                available,
+            } => write!(
+                f,
+                "only {} of {} robots fit on plain cells reachable from the station",
+                available, requested
+            ),
+            MapError::InvalidRobotPlacement { x, y, reason } => {
+                write!(f, "cannot place robot at ({}, {}): {}", x, y, reason)
+            }
+            MapError::ZoneOutsideMap {
+                zone,
+                width,
+                height,
+            } => write!(
+                f,
+                "no-go zone {},{},{}x{} does not fit inside the {}x{} map",
+                zone.x, zone.y, zone.width, zone.height, width, height
+            ),
+            MapError::Io(e) => write!(f, "cannot read map file: {}", e),
+            MapError::InvalidFile { line, reason } => {
+                write!(f, "invalid map file at line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Mirror,
+    Rotational,
+}
+
+impl ValueEnum for Symmetry {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Symmetry::None, Symmetry::Mirror, Symmetry::Rotational]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Symmetry::None => PossibleValue::new("none"),
+            Symmetry::Mirror => PossibleValue::new("mirror"),
+            Symmetry::Rotational => PossibleValue::new("rotational"),
+        })
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| format!("unknown symmetry '{}'", s))
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+// Independent sets of deposits. Each extra layer is generated from its own
+// sub-seed, so adding one never changes the surface of an existing world.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLayer {
+    Surface,
+    DeepScan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourcePlacement {
+    Noise,
+    PoissonDisk,
+}
+
+impl ValueEnum for ResourcePlacement {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ResourcePlacement::Noise, ResourcePlacement::PoissonDisk]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ResourcePlacement::Noise => PossibleValue::new("noise"),
+            ResourcePlacement::PoissonDisk => PossibleValue::new("poisson-disk").alias("poisson"),
+        })
+    }
+}
+
+impl FromStr for ResourcePlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+            .map_err(|_| format!("unknown resource placement '{}'", s))
+    }
+}
+
+impl fmt::Display for ResourcePlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapConstants {
+    pub obstacle_density: f64,
+    pub symmetry: Symmetry,
+    pub resource_abundance: f64,
+    pub resource_placement: ResourcePlacement,
+    pub erosion_iterations: usize,
+    pub smoothing_iterations: usize,
+}
+
+impl Default for MapConstants {
+    fn default() -> Self {
+        MapConstants {
+            obstacle_density: 0.5,
+            symmetry: Symmetry::None,
+            resource_abundance: 1.0,
+            resource_placement: ResourcePlacement::Noise,
+            erosion_iterations: 0,
+            smoothing_iterations: 0,
+        }
+    }
+}
+
+// Large enough for the biggest maps pathfinding is tuned for, small enough
+// that the per-cell grids fit comfortably in memory.
+pub const MAX_MAP_CELLS: usize = 4096 * 4096;
+
+const TERRAIN_SCALE: f64 = 0.1;
+const MAX_MOUNTAIN_FRACTION: f64 = 0.3;
+const MAX_HILL_FRACTION: f64 = 0.2;
+const MAX_CANYON_FRACTION: f64 = 0.1;
+const EROSION_RATE: f64 = 0.3;
+const SEDIMENT_CAPACITY: f64 = 4.0;
+const DROPLET_STEPS: usize = 32;
+const SMOOTHING_MAJORITY: usize = 5;
+const RESOURCE_SCALE: f64 = 0.3;
+const RESOURCE_THRESHOLD: f64 = 0.6;
+const DEEP_THRESHOLD: f64 = 0.75;
+// The deep layer's sub-seed comes from `derive_seed` like world rerolls do,
+// so its stream sits far above any reroll attempt.
+pub const DEEP_LAYER_STREAM: u64 = u32::MAX as u64;
+
 #[allow(dead_code)]
 pub struct Map {
     pub width: usize,
     pub height: usize,
     pub terrain: Vec<Vec<u8>>,
-    pub resources: HashMap<(usize, usize), (ResourceType, u32)>,
+    // Ordered so anything walking the deposits does so identically every run.
+    pub resources: BTreeMap<(usize, usize), (ResourceType, u32)>,
+    pub deep_resources: BTreeMap<(usize, usize), (ResourceType, u32)>,
     pub discovered: Vec<Vec<bool>>,
+    regions: Vec<Vec<Option<usize>>>,
+    no_go: Vec<Rect>,
     noise: Perlin,
     seed: u64,
+    terrain_version: u64,
+}
+
+#[allow(dead_code)]
+impl Map {
+    pub fn new(width: usize, height: usize, seed: u64) -> Result<Map, MapError> {
+        Self::with_progress(width, height, seed, &MapConstants::default(), |_, _, _| {})
+    }
+
+    pub fn with_progress<F>(
+        width: usize,
+        height: usize,
+        seed: u64,
+        constants: &MapConstants,
+        mut progress: F,
+    ) -> Result<Map, MapError>
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        if width == 0 || height == 0 {
+            return Err(MapError::InvalidDimensions { width, height });
+        }
+        if width
+            .checked_mul(height)
+            .is_none_or(|cells| cells > MAX_MAP_CELLS)
+        {
+            return Err(MapError::TooLarge { width, height });
+        }
+        if !(0.0..=1.0).contains(&constants.obstacle_density) {
+            return Err(MapError::InvalidObstacleDensity(constants.obstacle_density));
+        }
+        if !(constants.resource_abundance.is_finite() && constants.resource_abundance >= 0.0) {
+            return Err(MapError::InvalidResourceAbundance(
+                constants.resource_abundance,
+            ));
+        }
+
+        let mut map = Map {
+            width,
+            height,
+            terrain: vec![vec![0; width]; height],
+            resources: BTreeMap::new(),
+            deep_resources: BTreeMap::new(),
+            discovered: vec![vec![false; width]; height],
+            regions: vec![vec![None; width]; height],
+            no_go: Vec::new(),
+            noise: Perlin::new(noise_seed(seed)),
+            seed,
+            terrain_version: 0,
+        };
+
+        map.generate_terrain(constants, &mut progress);
+        map.smooth_terrain(constants.smoothing_iterations, &mut progress);
+        map.generate_resources(constants, &mut progress);
+        map.validate(&mut progress)?;
+        map.generate_deep_layer(constants, &mut progress);
+        map.refresh_regions();
+
+        Ok(map)
+    }
+
+    // Rebuilds a map from previously generated data, e.g. a saved map file.
+    // Nothing is regenerated, only the derived region lookup.
+    pub fn from_parts(
+        seed: u64,
+        terrain: Vec<Vec<u8>>,
+        resources: BTreeMap<(usize, usize), (ResourceType, u32)>,
+        deep_resources: BTreeMap<(usize, usize), (ResourceType, u32)>,
+        discovered: Vec<Vec<bool>>,
+    ) -> Result<Map, MapError> {
+        let height = terrain.len();
+        let width = terrain.first().map_or(0, Vec::len);
+        if width == 0 || height == 0 {
+            return Err(MapError::InvalidDimensions { width, height });
+        }
+
+        let mut map = Map {
+            width,
+            height,
+            terrain,
+            resources,
+            deep_resources,
+            discovered,
+            regions: Vec::new(),
+            no_go: Vec::new(),
+            noise: Perlin::new(noise_seed(seed)),
+            seed,
+            terrain_version: 0,
+        };
+        map.refresh_regions();
+
+        Ok(map)
+    }
+
+    // Must be called after any edit to `terrain` so region lookups stay valid
+    // and anything keyed on the terrain version, like cached paths, is dropped.
+    pub fn refresh_regions(&mut self) {
+        self.regions = self.traversable_regions().0;
+        self.terrain_version += 1;
+    }
+
+    pub fn terrain_version(&self) -> u64 {
+        self.terrain_version
+    }
+
+    pub fn region_of(&self, x: usize, y: usize) -> Option<usize> {
+        self.regions.get(y)?.get(x).copied().flatten()
+    }
+
+    // Cells in different traversable regions can never reach each other.
+    // Untraversable cells have no region, so they are left to the caller.
+    pub fn connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        match (self.region_of(a.0, a.1), self.region_of(b.0, b.1)) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    pub fn layer(&self, layer: ResourceLayer) -> &BTreeMap<(usize, usize), (ResourceType, u32)> {
+        match layer {
+            ResourceLayer::Surface => &self.resources,
+            ResourceLayer::DeepScan => &self.deep_resources,
+        }
+    }
+
+    // The first deposit at a cell across the layers a robot can sense.
+    pub fn resource_for(
+        &self,
+        x: usize,
+        y: usize,
+        sensors: &[ResourceLayer],
+    ) -> Option<&(ResourceType, u32)> {
+        sensors
+            .iter()
+            .find_map(|&layer| self.layer(layer).get(&(x, y)))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn terrain_at(&self, x: usize, y: usize) -> TerrainType {
+        TerrainType::from_u8(self.terrain[y][x])
+    }
+
+    // None for cells robots may not enter, either by terrain or no-go zone.
+    pub fn movement_cost(&self, x: usize, y: usize) -> Option<u32> {
+        if self.is_no_go(x, y) {
+            return None;
+        }
+        self.terrain_at(x, y).movement_cost()
+    }
+
+    pub fn add_no_go_zone(&mut self, zone: Rect) -> Result<(), MapError> {
+        if zone.width == 0 || zone.height == 0 || !zone.fits_within(self.width, self.height) {
+            return Err(MapError::ZoneOutsideMap {
+                zone,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        self.no_go.push(zone);
+        self.refresh_regions();
+        Ok(())
+    }
+
+    pub fn no_go_zones(&self) -> &[Rect] {
+        &self.no_go
+    }
+
+    pub fn is_no_go(&self, x: usize, y: usize) -> bool {
+        self.no_go.iter().any(|zone| zone.contains(x, y))
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn sample(&self, symmetry: Symmetry, x: usize, y: usize, scale: f64, offset: f64) -> f64 {
+        let (x, y) = self.canonical_cell(symmetry, x, y);
+        self.noise
+            .get([x as f64 * scale + offset, y as f64 * scale + offset])
+    }
+
+    // Maps a cell to its representative in the generated half so that both
+    // halves of a symmetric map sample identical noise.
+    fn canonical_cell(&self, symmetry: Symmetry, x: usize, y: usize) -> (usize, usize) {
+        let (mirror_x, mirror_y) = (self.width - 1 - x, self.height - 1 - y);
+
+        match symmetry {
+            Symmetry::None => (x, y),
+            Symmetry::Mirror => (x.min(mirror_x), y),
+            Symmetry::Rotational if (y, x) <= (mirror_y, mirror_x) => (x, y),
+            Symmetry::Rotational => (mirror_x, mirror_y),
+        }
+    }
+
+    fn generate_terrain<F>(&mut self, constants: &MapConstants, progress: &mut F)
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        let mut elevation = vec![vec![0.0; self.width]; self.height];
+        for (y, row) in elevation.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                *value = self.sample(constants.symmetry, x, y, TERRAIN_SCALE, 0.0);
+            }
+            progress(GenerationPhase::Terrain, y + 1, self.height);
+        }
+        self.erode(&mut elevation, constants, progress);
+
+        let mut sorted: Vec<f64> = elevation.iter().flatten().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+
+        let density = constants.obstacle_density;
+        let mountain_fraction = MAX_MOUNTAIN_FRACTION * density;
+        let hill_fraction = MAX_HILL_FRACTION * density;
+        let mountain_from = quantile(&sorted, 1.0 - mountain_fraction);
+        let hill_from = quantile(&sorted, 1.0 - mountain_fraction - hill_fraction);
+        let canyon_below = quantile(&sorted, MAX_CANYON_FRACTION * density);
+
+        for (y, row) in elevation.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                let terrain = if value >= mountain_from {
+                    TerrainType::Mountain
+                } else if value >= hill_from {
+                    TerrainType::Hill
+                } else if value < canyon_below {
+                    TerrainType::Canyon
+                } else {
+                    TerrainType::Plain
+                };
+
+                self.terrain[y][x] = terrain as u8;
+            }
+        }
+    }
+
+    // Hydraulic erosion: every iteration drops one droplet per cell that runs
+    // downhill, picking up material on steep steps and dropping it where the
+    // slope flattens, which carves valleys and canyon networks. Symmetric maps
+    // copy the canonical half back afterwards since the droplets are not.
+    fn erode<F>(&self, elevation: &mut [Vec<f64>], constants: &MapConstants, progress: &mut F)
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        let iterations = constants.erosion_iterations;
+        for iteration in 0..iterations {
+            for start_y in 0..self.height {
+                for start_x in 0..self.width {
+                    let (mut x, mut y) = (start_x, start_y);
+                    let mut sediment = 0.0;
+
+                    for _ in 0..DROPLET_STEPS {
+                        let lowest = self
+                            .neighbors(x, y)
+                            .min_by(|a, b| elevation[a.1][a.0].total_cmp(&elevation[b.1][b.0]));
+                        let Some((nx, ny)) = lowest else {
+                            break;
+                        };
+                        let drop = elevation[y][x] - elevation[ny][nx];
+                        if drop <= 0.0 {
+                            break;
+                        }
+
+                        let capacity = drop * SEDIMENT_CAPACITY;
+                        if sediment > capacity {
+                            let deposit = (sediment - capacity).min(drop / 2.0);
+                            elevation[y][x] += deposit;
+                            sediment -= deposit;
+                        } else {
+                            let eroded = ((capacity - sediment) * EROSION_RATE).min(drop / 2.0);
+                            elevation[y][x] -= eroded;
+                            sediment += eroded;
+                        }
+                        (x, y) = (nx, ny);
+                    }
+
+                    elevation[y][x] += sediment;
+                }
+            }
+            progress(GenerationPhase::Erosion, iteration + 1, iterations);
+        }
+
+        if iterations > 0 && constants.symmetry != Symmetry::None {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let (cx, cy) = self.canonical_cell(constants.symmetry, x, y);
+                    elevation[y][x] = elevation[cy][cx];
+                }
+            }
+        }
+    }
+
+    // Majority filter over each 3x3 block: a cell takes the terrain held by at
+    // least five of the nine cells, which clears single-cell speckles. A
+    // strict majority has a unique winner, so symmetric maps stay symmetric.
+    fn smooth_terrain<F>(&mut self, iterations: usize, progress: &mut F)
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        for iteration in 0..iterations {
+            let mut smoothed = self.terrain.clone();
+            for (y, row) in smoothed.iter_mut().enumerate() {
+                for (x, cell) in row.iter_mut().enumerate() {
+                    let mut counts = [0; 4];
+                    for ny in y.saturating_sub(1)..=(y + 1).min(self.height - 1) {
+                        for nx in x.saturating_sub(1)..=(x + 1).min(self.width - 1) {
+                            counts[self.terrain_at(nx, ny) as usize] += 1;
+                        }
+                    }
+                    if let Some(terrain) =
+                        counts.iter().position(|&count| count >= SMOOTHING_MAJORITY)
+                    {
+                        *cell = terrain as u8;
+                    }
+                }
+            }
+            self.terrain = smoothed;
+            progress(GenerationPhase::Smoothing, iteration + 1, iterations);
+        }
+    }
+
+    fn generate_resources<F>(&mut self, constants: &MapConstants, progress: &mut F)
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        let mut candidates = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.terrain_at(x, y).is_traversable() {
+                    let density = self.sample(constants.symmetry, x, y, RESOURCE_SCALE, 100.0);
+                    candidates.push((x, y, density));
+                }
+            }
+            progress(GenerationPhase::Resources, y + 1, self.height);
+        }
+
+        // Abundance scales how many of the densest cells get a deposit,
+        // relative to the count the fixed threshold would give.
+        let abundance = constants.resource_abundance;
+        let base_count = candidates
+            .iter()
+            .filter(|&&(_, _, density)| density >= RESOURCE_THRESHOLD)
+            .count();
+        let mut count = ((base_count as f64 * abundance).round() as usize).min(candidates.len());
+
+        candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+        // Extend the cut over equal densities so mirrored cells stay paired.
+        while count > 0
+            && count < candidates.len()
+            && candidates[count].2 == candidates[count - 1].2
+        {
+            count += 1;
+        }
+
+        let chosen = match constants.resource_placement {
+            ResourcePlacement::Noise => candidates[..count].to_vec(),
+            ResourcePlacement::PoissonDisk => {
+                self.poisson_disk_sample(constants.symmetry, &candidates, count)
+            }
+        };
+
+        for (x, y, density) in chosen {
+            let resource_type = self.resource_type_at(constants.symmetry, x, y);
+
+            let base_amount = 10.0 + (density - RESOURCE_THRESHOLD).max(0.0) * 200.0;
+            let amount = (base_amount * abundance).round().max(1.0) as u32;
+            self.resources.insert((x, y), (resource_type, amount));
+        }
+    }
+
+    // Sparse, richer deposits hidden under traversable cells that have no
+    // surface deposit. Runs after validation so sealed pockets stay empty.
+    fn generate_deep_layer<F>(&mut self, constants: &MapConstants, progress: &mut F)
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        let noise = Perlin::new(derive_seed(self.seed, DEEP_LAYER_STREAM) as u32);
+        let sample = |x: usize, y: usize, offset: f64| {
+            let (x, y) = self.canonical_cell(constants.symmetry, x, y);
+            noise.get([
+                x as f64 * RESOURCE_SCALE + offset,
+                y as f64 * RESOURCE_SCALE + offset,
+            ])
+        };
+
+        let mut deposits = BTreeMap::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.terrain_at(x, y).is_traversable() || self.resources.contains_key(&(x, y)) {
+                    continue;
+                }
+                let density = sample(x, y, 0.0);
+                if density < DEEP_THRESHOLD || constants.resource_abundance == 0.0 {
+                    continue;
+                }
+
+                let resource_type = if sample(x, y, 100.0) < 0.0 {
+                    ResourceType::Mineral
+                } else {
+                    ResourceType::ScientificInterest
+                };
+                let base_amount = 40.0 + (density - DEEP_THRESHOLD) * 400.0;
+                let amount = (base_amount * constants.resource_abundance)
+                    .round()
+                    .max(1.0) as u32;
+                deposits.insert((x, y), (resource_type, amount));
+            }
+            progress(GenerationPhase::DeepScan, y + 1, self.height);
+        }
+
+        self.deep_resources = deposits;
+    }
+
+    fn resource_type_at(&self, symmetry: Symmetry, x: usize, y: usize) -> ResourceType {
+        let kind = self.sample(symmetry, x, y, RESOURCE_SCALE, 200.0);
+        if kind < -0.1 {
+            ResourceType::Energy
+        } else if kind < 0.2 {
+            ResourceType::Mineral
+        } else {
+            ResourceType::ScientificInterest
+        }
+    }
+
+    // Dart throwing over the candidates in a seeded pseudo-random order,
+    // rejecting any cell closer than the minimum spacing to a deposit of the
+    // same type. Cells sharing a canonical cell are accepted or rejected
+    // together so symmetric maps keep matching deposits.
+    fn poisson_disk_sample(
+        &self,
+        symmetry: Symmetry,
+        candidates: &[(usize, usize, f64)],
+        count: usize,
+    ) -> Vec<(usize, usize, f64)> {
+        let mut order: Vec<_> = candidates
+            .iter()
+            .map(|&(x, y, density)| {
+                let canonical = self.canonical_cell(symmetry, x, y);
+                let key = cell_hash(self.seed, canonical.0, canonical.1);
+                (key, canonical, (x, y, density))
+            })
+            .collect();
+        order.sort_by_key(|&(key, canonical, (x, y, _))| (key, canonical, x, y));
+
+        let mut placed: Vec<Option<ResourceType>> = vec![None; self.width * self.height];
+        let mut chosen = Vec::new();
+
+        for group in order.chunk_by(|a, b| a.1 == b.1) {
+            if chosen.len() >= count {
+                break;
+            }
+
+            let cells: Vec<_> = group.iter().map(|&(_, _, cell)| cell).collect();
+            let resource_type = self.resource_type_at(symmetry, cells[0].0, cells[0].1);
+            let spacing = min_spacing(&resource_type);
+            let crowded = cells.iter().any(|&(x, y, _)| {
+                let (min_x, max_x) = (x.saturating_sub(spacing), (x + spacing).min(self.width - 1));
+                let (min_y, max_y) = (
+                    y.saturating_sub(spacing),
+                    (y + spacing).min(self.height - 1),
+                );
+                (min_y..=max_y).any(|ny| {
+                    (min_x..=max_x).any(|nx| {
+                        let (dx, dy) = (nx.abs_diff(x), ny.abs_diff(y));
+                        dx * dx + dy * dy < spacing * spacing
+                            && placed[ny * self.width + nx].as_ref() == Some(&resource_type)
+                    })
+                })
+            });
+            if crowded {
+                continue;
+            }
+
+            for &(x, y, density) in &cells {
+                placed[y * self.width + x] = Some(resource_type.clone());
+                chosen.push((x, y, density));
+            }
+        }
+
+        chosen
+    }
+
+    fn validate<F>(&mut self, progress: &mut F) -> Result<(), MapError>
+    where
+        F: FnMut(GenerationPhase, usize, usize),
+    {
+        let (regions, sizes) = self.traversable_regions();
+        let largest = sizes.iter().copied().max();
+        progress(GenerationPhase::Validation, 1, 2);
+
+        let Some(largest) = largest else {
+            return Err(MapError::NoTraversableTerrain);
+        };
+
+        // Seal off pockets that cannot reach the main region so every
+        // traversable cell stays reachable from every other one. Regions tied
+        // for largest are all kept, otherwise a symmetric map whose halves are
+        // disconnected would lose one of them.
+        for (y, row) in regions.iter().enumerate() {
+            for (x, &region) in row.iter().enumerate() {
+                if matches!(region, Some(region) if sizes[region] < largest) {
+                    self.terrain[y][x] = TerrainType::Mountain as u8;
+                    self.resources.remove(&(x, y));
+                }
+            }
+        }
+        progress(GenerationPhase::Validation, 2, 2);
+
+        Ok(())
+    }
+
+    fn traversable_regions(&self) -> (Vec<Vec<Option<usize>>>, Vec<usize>) {
+        let mut regions = vec![vec![None; self.width]; self.height];
+        let mut sizes = Vec::new();
+
+        for start_y in 0..self.height {
+            for start_x in 0..self.width {
+                if regions[start_y][start_x].is_some()
+                    || self.movement_cost(start_x, start_y).is_none()
+                {
+                    continue;
+                }
+
+                let region = sizes.len();
+                let mut size = 0;
+                let mut stack = vec![(start_x, start_y)];
+                regions[start_y][start_x] = Some(region);
+
+                while let Some((x, y)) = stack.pop() {
+                    size += 1;
+                    for (nx, ny) in self.neighbors(x, y) {
+                        if regions[ny][nx].is_none() && self.movement_cost(nx, ny).is_some() {
+                            regions[ny][nx] = Some(region);
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+
+                sizes.push(size);
+            }
+        }
+
+        (regions, sizes)
+    }
+
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| {
+                let nx = x.checked_add_signed(dx)?;
+                let ny = y.checked_add_signed(dy)?;
+                self.in_bounds(nx, ny).then_some((nx, ny))
+            })
+    }
+}
+
+fn min_spacing(resource_type: &ResourceType) -> usize {
+    match resource_type {
+        ResourceType::Energy => 3,
+        ResourceType::Mineral => 4,
+        ResourceType::ScientificInterest => 6,
+    }
+}
+
+// Seed for the `attempt`-th reroll of a world, unrelated to nearby seeds.
+pub fn derive_seed(seed: u64, attempt: u64) -> u64 {
+    cell_hash(seed, attempt as usize, 0)
+}
+
+// Perlin only takes 32 bits, so the whole seed is mixed in first. Plain
+// truncation gave seeds 2^32 apart identical terrain.
+fn noise_seed(seed: u64) -> u32 {
+    (cell_hash(seed, 0, 0) >> 32) as u32
+}
+
+// SplitMix64 over the seed and cell, stable across platforms and releases.
+fn cell_hash(seed: u64, x: usize, y: usize) -> u64 {
+    let mut z = seed ^ ((x as u64) << 32 | y as u64);
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn quantile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = (sorted.len() as f64 * fraction).round() as usize;
+    sorted.get(rank).copied().unwrap_or(f64::INFINITY)
 }
 
 #[allow(dead_code)]
@@ -28,14 +934,132 @@ pub enum RobotType {
     Scientist,
 }
 
+impl RobotType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RobotType::Explorer => "explorer",
+            RobotType::Harvester => "harvester",
+            RobotType::Scientist => "scientist",
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn sensors(&self) -> &'static [ResourceLayer] {
+        match self {
+            RobotType::Scientist => &[ResourceLayer::Surface, ResourceLayer::DeepScan],
+            RobotType::Explorer | RobotType::Harvester => &[ResourceLayer::Surface],
+        }
+    }
+
+    // Movement points per tick; a plain cell costs one.
+    pub fn speed(&self) -> usize {
+        match self {
+            RobotType::Explorer => 3,
+            RobotType::Harvester => 1,
+            RobotType::Scientist => 2,
+        }
+    }
+}
+
+impl ValueEnum for RobotType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            RobotType::Explorer,
+            RobotType::Harvester,
+            RobotType::Scientist,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+impl FromStr for RobotType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| format!("unknown robot type '{}'", s))
+    }
+}
+
+impl fmt::Display for RobotType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RobotId(pub usize);
+
+impl fmt::Display for RobotId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "robot-{}", self.0)
+    }
+}
+
 #[allow(dead_code)]
 pub struct Robot {
-    pub id: usize,
+    pub id: RobotId,
     pub robot_type: RobotType,
     pub x: usize,
     pub y: usize,
     pub energy: u32,
     pub carrying: Option<(ResourceType, u32)>,
+    pub movement_points: u32,
+    pub movement_cost: u64,
+}
+
+#[allow(dead_code)]
+impl Robot {
+    // Walks along `path`, which starts at the robot's current cell, spending
+    // `speed` movement points per tick and each cell's movement cost in both
+    // points and energy. Points left short of a costly cell carry over, so a
+    // slow robot crosses a hill over several ticks. Stops early when the next
+    // step is blocked or unaffordable and returns the cells moved.
+    pub fn advance(&mut self, path: &[(usize, usize)], map: &Map) -> usize {
+        self.advance_while(path, map, |_, _| true)
+    }
+
+    pub fn advance_while<F>(
+        &mut self,
+        path: &[(usize, usize)],
+        map: &Map,
+        mut can_enter: F,
+    ) -> usize
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let Some(start) = path.iter().position(|&cell| cell == (self.x, self.y)) else {
+            self.movement_points = 0;
+            return 0;
+        };
+
+        let mut points = self.movement_points + self.robot_type.speed() as u32;
+        self.movement_points = 0;
+
+        let mut moved = 0;
+        for &(x, y) in &path[start + 1..] {
+            let Some(cost) = map.movement_cost(x, y) else {
+                break;
+            };
+            if cost > self.energy || !can_enter(x, y) {
+                break;
+            }
+            if cost > points {
+                self.movement_points = points;
+                break;
+            }
+
+            points -= cost;
+            self.energy -= cost;
+            self.movement_cost += u64::from(cost);
+            self.x = x;
+            self.y = y;
+            moved += 1;
+        }
+        moved
+    }
 }
 
 #[allow(dead_code)]
@@ -45,3 +1069,349 @@ pub struct Station {
     pub x: usize,
     pub y: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_differing_only_in_high_bits_give_different_terrain() {
+        let low = Map::new(40, 20, 42).unwrap();
+        let high = Map::new(40, 20, 42 + (1 << 32)).unwrap();
+        assert_ne!(low.terrain, high.terrain);
+    }
+
+    #[test]
+    fn no_go_zones_must_fit_inside_the_map() {
+        let mut map = Map::new(20, 10, 7).unwrap();
+        let zone = |x, y, width, height| Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        assert!(map.add_no_go_zone(zone(15, 5, 5, 5)).is_ok());
+        assert!(map.add_no_go_zone(zone(16, 5, 5, 5)).is_err());
+        assert!(map.add_no_go_zone(zone(0, 10, 1, 1)).is_err());
+        assert!(map.add_no_go_zone(zone(3, 3, 0, 2)).is_err());
+        assert!(map.add_no_go_zone(zone(usize::MAX, 0, 5, 5)).is_err());
+        assert_eq!(map.no_go_zones(), &[zone(15, 5, 5, 5)]);
+    }
+
+    #[test]
+    fn resource_types_round_trip_through_their_names() {
+        for resource_type in ResourceType::value_variants() {
+            let name = resource_type.to_string();
+            assert_eq!(&name.parse::<ResourceType>().unwrap(), resource_type);
+            assert_eq!(
+                &name.to_uppercase().parse::<ResourceType>().unwrap(),
+                resource_type
+            );
+        }
+        assert_eq!(
+            "science".parse::<ResourceType>(),
+            Ok(ResourceType::ScientificInterest)
+        );
+        assert!("gold".parse::<ResourceType>().is_err());
+    }
+
+    #[test]
+    fn robot_types_round_trip_through_their_names() {
+        for robot_type in RobotType::value_variants() {
+            let name = robot_type.to_string();
+            assert_eq!(&name.parse::<RobotType>().unwrap(), robot_type);
+            let value = robot_type.to_possible_value().unwrap();
+            assert_eq!(value.get_name(), name);
+        }
+        assert!("drone".parse::<RobotType>().is_err());
+    }
+
+    #[test]
+    fn generation_settings_round_trip_through_their_names() {
+        for symmetry in Symmetry::value_variants() {
+            assert_eq!(&symmetry.to_string().parse::<Symmetry>().unwrap(), symmetry);
+        }
+        for placement in ResourcePlacement::value_variants() {
+            let name = placement.to_string();
+            assert_eq!(&name.parse::<ResourcePlacement>().unwrap(), placement);
+        }
+        assert_eq!(
+            "poisson".parse::<ResourcePlacement>(),
+            Ok(ResourcePlacement::PoissonDisk)
+        );
+        assert!("radial".parse::<Symmetry>().is_err());
+    }
+
+    #[test]
+    fn symmetric_maps_match_across_halves_after_erosion_and_smoothing() {
+        for symmetry in [Symmetry::Mirror, Symmetry::Rotational] {
+            for resource_placement in ResourcePlacement::value_variants() {
+                let constants = MapConstants {
+                    symmetry,
+                    resource_placement: *resource_placement,
+                    erosion_iterations: 3,
+                    smoothing_iterations: 2,
+                    ..MapConstants::default()
+                };
+                let map = Map::with_progress(41, 30, 9, &constants, |_, _, _| {}).unwrap();
+                let mirror = |x: usize, y: usize| match symmetry {
+                    Symmetry::Rotational => (map.width - 1 - x, map.height - 1 - y),
+                    _ => (map.width - 1 - x, y),
+                };
+
+                for y in 0..map.height {
+                    for x in 0..map.width {
+                        let (mx, my) = mirror(x, y);
+                        assert_eq!(map.terrain[y][x], map.terrain[my][mx], "{}", symmetry);
+                        for layer in [&map.resources, &map.deep_resources] {
+                            assert_eq!(layer.get(&(x, y)), layer.get(&(mx, my)), "{}", symmetry);
+                        }
+                    }
+                }
+                assert!(!map.resources.is_empty());
+            }
+        }
+    }
+
+    fn generated(seed: u64, constants: &MapConstants) -> Map {
+        Map::with_progress(48, 32, seed, constants, |_, _, _| {}).unwrap()
+    }
+
+    fn terrain_count(map: &Map, terrain: TerrainType) -> usize {
+        map.terrain
+            .iter()
+            .flatten()
+            .filter(|&&cell| TerrainType::from_u8(cell) == terrain)
+            .count()
+    }
+
+    #[test]
+    fn obstacle_density_scales_obstacles_and_keeps_maps_connected() {
+        for seed in [1, 42, 1234] {
+            let mut shares = Vec::new();
+            for obstacle_density in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                let constants = MapConstants {
+                    obstacle_density,
+                    ..MapConstants::default()
+                };
+                let map = generated(seed, &constants);
+                let obstacles = terrain_count(&map, TerrainType::Mountain)
+                    + terrain_count(&map, TerrainType::Hill)
+                    + terrain_count(&map, TerrainType::Canyon);
+                if obstacle_density == 0.0 {
+                    assert_eq!(terrain_count(&map, TerrainType::Mountain), 0);
+                }
+                shares.push(obstacles);
+
+                let (_, sizes) = map.traversable_regions();
+                assert_eq!(sizes.len(), 1, "seed {} at {}", seed, obstacle_density);
+            }
+            assert!(
+                shares.windows(2).all(|pair| pair[0] <= pair[1]),
+                "seed {}",
+                seed
+            );
+            assert!(shares[0] < shares[shares.len() - 1], "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn poisson_disk_deposits_of_a_type_keep_their_spacing() {
+        for seed in [3, 42, 77] {
+            let constants = MapConstants {
+                resource_placement: ResourcePlacement::PoissonDisk,
+                resource_abundance: 4.0,
+                ..MapConstants::default()
+            };
+            let map = generated(seed, &constants);
+            assert!(!map.resources.is_empty());
+
+            for (&(x, y), (resource_type, _)) in &map.resources {
+                let spacing = min_spacing(resource_type);
+                for (&(ox, oy), (other_type, _)) in &map.resources {
+                    if (ox, oy) == (x, y) || other_type != resource_type {
+                        continue;
+                    }
+                    let (dx, dy) = (ox.abs_diff(x), oy.abs_diff(y));
+                    assert!(
+                        dx * dx + dy * dy >= spacing * spacing,
+                        "seed {}: {} at ({}, {}) and ({}, {})",
+                        seed,
+                        resource_type,
+                        x,
+                        y,
+                        ox,
+                        oy
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resource_abundance_scales_deposits_and_units() {
+        let units = |map: &Map| -> u64 {
+            map.resources
+                .values()
+                .map(|&(_, amount)| u64::from(amount))
+                .sum()
+        };
+
+        // Only the surface is compared across abundances: deep deposits fill
+        // the cells the surface leaves free, so they can shrink as it grows.
+        for resource_placement in ResourcePlacement::value_variants() {
+            let mut previous: Option<(usize, u64)> = None;
+            for resource_abundance in [0.0, 0.5, 1.0, 2.0, 5.0] {
+                let constants = MapConstants {
+                    resource_abundance,
+                    resource_placement: *resource_placement,
+                    ..MapConstants::default()
+                };
+                let map = generated(11, &constants);
+                let deposits = map.resources.len();
+                if resource_abundance == 0.0 {
+                    assert!(map.resources.is_empty() && map.deep_resources.is_empty());
+                }
+                if let Some((fewest, least)) = previous {
+                    assert!(
+                        deposits >= fewest,
+                        "{} at {}",
+                        resource_placement,
+                        resource_abundance
+                    );
+                    assert!(
+                        units(&map) >= least,
+                        "{} at {}",
+                        resource_placement,
+                        resource_abundance
+                    );
+                }
+                previous = Some((deposits, units(&map)));
+            }
+        }
+    }
+
+    #[test]
+    fn smoothing_clears_single_cell_speckles() {
+        let mut terrain = vec![vec![TerrainType::Plain as u8; 9]; 9];
+        terrain[2][2] = TerrainType::Mountain as u8;
+        for row in &mut terrain[5..] {
+            row[5..].fill(TerrainType::Hill as u8);
+        }
+        let mut map = Map::from_parts(
+            0,
+            terrain,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; 9]; 9],
+        )
+        .unwrap();
+
+        map.smooth_terrain(1, &mut |_, _, _| {});
+        assert_eq!(map.terrain_at(2, 2), TerrainType::Plain);
+        assert_eq!(map.terrain_at(7, 7), TerrainType::Hill);
+        assert_eq!(terrain_count(&map, TerrainType::Mountain), 0);
+    }
+
+    #[test]
+    fn erosion_changes_terrain_only_when_it_runs() {
+        let map = generated(5, &MapConstants::default());
+        let elevation: Vec<Vec<f64>> = (0..map.height)
+            .map(|y| {
+                (0..map.width)
+                    .map(|x| map.sample(Symmetry::None, x, y, TERRAIN_SCALE, 0.0))
+                    .collect()
+            })
+            .collect();
+        let mut unchanged = elevation.clone();
+        map.erode(&mut unchanged, &MapConstants::default(), &mut |_, _, _| {});
+        assert_eq!(unchanged, elevation);
+
+        let eroded = MapConstants {
+            erosion_iterations: 3,
+            ..MapConstants::default()
+        };
+        let first = generated(5, &eroded);
+        assert_ne!(first.terrain, map.terrain);
+        assert_eq!(generated(5, &eroded).terrain, first.terrain);
+    }
+
+    #[test]
+    fn the_deep_layer_only_fills_cells_the_surface_leaves_free() {
+        for symmetry in Symmetry::value_variants() {
+            let constants = MapConstants {
+                symmetry: *symmetry,
+                ..MapConstants::default()
+            };
+            let mut map = generated(21, &constants);
+            let surface = map.resources.clone();
+            let deep = map.deep_resources.clone();
+            assert!(!deep.is_empty());
+
+            map.generate_deep_layer(&constants, &mut |_, _, _| {});
+            assert_eq!(map.resources, surface);
+            assert_eq!(map.deep_resources, deep);
+            for &(x, y) in deep.keys() {
+                assert!(
+                    !surface.contains_key(&(x, y)),
+                    "{} at ({}, {})",
+                    symmetry,
+                    x,
+                    y
+                );
+                assert!(map.terrain_at(x, y).is_traversable());
+            }
+        }
+    }
+
+    fn robot(robot_type: RobotType) -> Robot {
+        Robot {
+            id: RobotId(0),
+            robot_type,
+            x: 0,
+            y: 0,
+            energy: 100,
+            carrying: None,
+            movement_points: 0,
+            movement_cost: 0,
+        }
+    }
+
+    #[test]
+    fn robots_spend_ticks_and_energy_by_terrain_cost() {
+        let row = [TerrainType::Plain, TerrainType::Canyon]
+            .into_iter()
+            .chain([TerrainType::Plain; 7])
+            .map(|terrain| terrain as u8)
+            .collect();
+        let map = Map::from_parts(
+            0,
+            vec![row],
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; 9]],
+        )
+        .unwrap();
+        let path: Vec<(usize, usize)> = (0..9).map(|x| (x, 0)).collect();
+
+        let mut harvester = robot(RobotType::Harvester);
+        assert_eq!(harvester.advance_while(&path, &map, |_, _| true), 0);
+        assert_eq!(harvester.advance_while(&path, &map, |_, _| true), 0);
+        assert_eq!((harvester.energy, harvester.movement_cost), (100, 0));
+        assert_eq!(harvester.advance_while(&path, &map, |_, _| true), 1);
+        assert_eq!((harvester.x, harvester.y), (1, 0));
+        assert_eq!((harvester.energy, harvester.movement_cost), (97, 3));
+
+        let mut explorer = robot(RobotType::Explorer);
+        explorer.x = 2;
+        assert_eq!(explorer.advance_while(&path, &map, |_, _| true), 3);
+        assert_eq!(explorer.advance_while(&path, &map, |_, _| true), 3);
+        assert_eq!((explorer.x, explorer.y), (8, 0));
+        assert_eq!((explorer.energy, explorer.movement_cost), (94, 6));
+
+        let mut blocked = robot(RobotType::Explorer);
+        blocked.x = 2;
+        assert_eq!(blocked.advance_while(&path, &map, |x, _| x < 4), 1);
+        assert_eq!(blocked.energy, 99);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::simulation::entities::Map;
+
+const DEFAULT_DECAY: f32 = 0.9;
+const NEGLIGIBLE_INFLUENCE: f32 = 0.01;
+
+// Shared record of where robots have recently been or are heading. Each stamp
+// fades linearly with distance and the whole grid decays every tick, so
+// explorers picking the least influenced frontier naturally spread out.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct InfluenceMap {
+    width: usize,
+    height: usize,
+    decay: f32,
+    values: Vec<f32>,
+}
+
+#[allow(dead_code)]
+impl InfluenceMap {
+    pub fn new(map: &Map) -> InfluenceMap {
+        InfluenceMap {
+            width: map.width,
+            height: map.height,
+            decay: DEFAULT_DECAY,
+            values: vec![0.0; map.width * map.height],
+        }
+    }
+
+    pub fn with_decay(mut self, decay: f32) -> InfluenceMap {
+        self.decay = decay.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn at(&self, x: usize, y: usize) -> f32 {
+        if x >= self.width || y >= self.height {
+            return 0.0;
+        }
+        self.values[y * self.width + x]
+    }
+
+    pub fn stamp(&mut self, x: usize, y: usize, radius: usize, strength: f32) {
+        let (min_x, max_x) = (x.saturating_sub(radius), (x + radius).min(self.width - 1));
+        let (min_y, max_y) = (y.saturating_sub(radius), (y + radius).min(self.height - 1));
+
+        for cy in min_y..=max_y {
+            for cx in min_x..=max_x {
+                let distance = cx.abs_diff(x) + cy.abs_diff(y);
+                if distance > radius {
+                    continue;
+                }
+                let falloff = 1.0 - distance as f32 / (radius + 1) as f32;
+                self.values[cy * self.width + cx] += strength * falloff;
+            }
+        }
+    }
+
+    pub fn decay(&mut self) {
+        for value in &mut self.values {
+            *value *= self.decay;
+            if *value < NEGLIGIBLE_INFLUENCE {
+                *value = 0.0;
+            }
+        }
+    }
+
+    // Ties keep the earliest candidate so every robot agrees on the choice.
+    pub fn least_influenced<I>(&self, candidates: I) -> Option<(usize, usize)>
+    where
+        I: IntoIterator<Item = (usize, usize)>,
+    {
+        candidates
+            .into_iter()
+            .fold(None, |best: Option<((usize, usize), f32)>, cell| {
+                let value = self.at(cell.0, cell.1);
+                match best {
+                    Some((_, best_value)) if best_value <= value => best,
+                    _ => Some((cell, value)),
+                }
+            })
+            .map(|(cell, _)| cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn influence() -> InfluenceMap {
+        InfluenceMap::new(&Map::new(20, 10, 1).unwrap())
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn stamp_fades_with_distance_and_stays_in_radius() {
+        let mut influence = influence();
+        influence.stamp(5, 5, 2, 3.0);
+
+        assert_near(influence.at(5, 5), 3.0);
+        assert_near(influence.at(6, 5), 2.0);
+        assert_near(influence.at(6, 6), 1.0);
+        assert_eq!(influence.at(8, 5), 0.0);
+        assert_eq!(influence.at(6, 7), 0.0);
+    }
+
+    #[test]
+    fn stamp_near_the_edge_is_clipped() {
+        let mut influence = influence();
+        influence.stamp(0, 0, 3, 1.0);
+        influence.stamp(19, 9, 3, 1.0);
+
+        assert_eq!(influence.at(0, 0), 1.0);
+        assert_eq!(influence.at(19, 9), 1.0);
+        assert_eq!(influence.at(25, 25), 0.0);
+    }
+
+    #[test]
+    fn decay_fades_stamps_to_zero() {
+        let mut influence = influence().with_decay(0.5);
+        influence.stamp(5, 5, 0, 1.0);
+
+        influence.decay();
+        assert_eq!(influence.at(5, 5), 0.5);
+        for _ in 0..10 {
+            influence.decay();
+        }
+        assert_eq!(influence.at(5, 5), 0.0);
+    }
+
+    #[test]
+    fn least_influenced_prefers_quiet_cells_and_the_first_of_ties() {
+        let mut influence = influence();
+        influence.stamp(2, 2, 2, 1.0);
+
+        assert_eq!(
+            influence.least_influenced([(2, 2), (3, 2), (15, 5), (16, 5)]),
+            Some((15, 5))
+        );
+        assert_eq!(influence.least_influenced([(2, 2)]), Some((2, 2)));
+        assert_eq!(influence.least_influenced([]), None);
+    }
+}
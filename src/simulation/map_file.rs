@@ -0,0 +1,510 @@
+use crate::simulation::entities::{Map, MapError, ResourceType, TerrainType};
+use crate::simulation::view::Rect;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+const FORMAT_HEADER: &str = "nova-map 2";
+
+// The largest size a map file may declare. Checked against the size line
+// before anything is allocated, so a corrupt or hostile file cannot make the
+// loader reserve more than this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapLimits {
+    pub max_width: usize,
+    pub max_height: usize,
+}
+
+impl Default for MapLimits {
+    fn default() -> MapLimits {
+        MapLimits {
+            max_width: 4096,
+            max_height: 4096,
+        }
+    }
+}
+
+impl FromStr for MapLimits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected <width>x<height>, got '{}'", s);
+        let number = |value: &str| value.trim().parse().map_err(|_| usage());
+
+        let (width, height) = s.split_once('x').ok_or_else(usage)?;
+        let limits = MapLimits {
+            max_width: number(width)?,
+            max_height: number(height)?,
+        };
+        if limits.max_width == 0 || limits.max_height == 0 {
+            return Err(format!("'{}' allows no cells", s));
+        }
+        Ok(limits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Resources,
+    Deep,
+    NoGo,
+}
+
+impl Section {
+    fn from_keyword(keyword: &str) -> Option<Section> {
+        match keyword {
+            "resources" => Some(Section::Resources),
+            "deep" => Some(Section::Deep),
+            "no-go" => Some(Section::NoGo),
+            _ => None,
+        }
+    }
+}
+
+// Plain text so saved maps can be diffed and edited by hand: a header, the
+// terrain and the discovered cells as one run-length encoded row per line,
+// then one line per deposit and no-go zone. A run is a glyph with an
+// optional repeat count in front, so "12.n3~" is twelve plains, a hill and
+// three canyons.
+impl Map {
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}", FORMAT_HEADER)?;
+        writeln!(out, "size {} {}", self.width, self.height)?;
+        writeln!(out, "seed {}", self.seed())?;
+        writeln!(out, "terrain")?;
+        for row in &self.terrain {
+            let glyphs = row
+                .iter()
+                .map(|&value| terrain_glyph(TerrainType::from_u8(value)));
+            writeln!(out, "{}", encode_runs(glyphs))?;
+        }
+        writeln!(out, "discovered")?;
+        for row in &self.discovered {
+            let glyphs = row
+                .iter()
+                .map(|&discovered| if discovered { '#' } else { '.' });
+            writeln!(out, "{}", encode_runs(glyphs))?;
+        }
+        for (keyword, deposits) in [
+            ("resources", &self.resources),
+            ("deep", &self.deep_resources),
+        ] {
+            writeln!(out, "{}", keyword)?;
+            for (&(x, y), (resource_type, amount)) in deposits {
+                writeln!(out, "{} {} {} {}", x, y, resource_type.name(), amount)?;
+            }
+        }
+        writeln!(out, "no-go")?;
+        for zone in self.no_go_zones() {
+            writeln!(out, "{},{},{}x{}", zone.x, zone.y, zone.width, zone.height)?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn load_from_file(path: &Path) -> Result<Map, MapError> {
+        Map::load_from_file_with(path, MapLimits::default())
+    }
+
+    pub fn load_from_file_with(path: &Path, limits: MapLimits) -> Result<Map, MapError> {
+        let text = fs::read_to_string(path).map_err(MapError::Io)?;
+        parse(&text, limits)
+    }
+}
+
+fn parse(text: &str, limits: MapLimits) -> Result<Map, MapError> {
+    let end = text.lines().count() + 1;
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()));
+
+    let (line, header) = lines.next().unwrap_or((1, ""));
+    if header != FORMAT_HEADER {
+        return Err(invalid(line, format!("expected '{}'", FORMAT_HEADER)));
+    }
+
+    let (line, size) = keyed(lines.next(), "size", end)?;
+    let [width, height] = size[..] else {
+        return Err(invalid(line, "expected 'size <width> <height>'"));
+    };
+    let (width, height): (usize, usize) = (number(line, width)?, number(line, height)?);
+    if width == 0 || height == 0 {
+        return Err(MapError::InvalidDimensions { width, height });
+    }
+    if width > limits.max_width || height > limits.max_height {
+        return Err(invalid(
+            line,
+            format!(
+                "{}x{} is larger than the {}x{} limit",
+                width, height, limits.max_width, limits.max_height
+            ),
+        ));
+    }
+
+    let (line, seed) = keyed(lines.next(), "seed", end)?;
+    let [seed] = seed[..] else {
+        return Err(invalid(line, "expected 'seed <seed>'"));
+    };
+    let seed: u64 = number(line, seed)?;
+
+    keyed(lines.next(), "terrain", end)?;
+    let terrain = grid(
+        &mut lines,
+        "terrain",
+        (width, height),
+        end,
+        terrain_from_glyph,
+    )?;
+    keyed(lines.next(), "discovered", end)?;
+    let discovered = grid(
+        &mut lines,
+        "discovered",
+        (width, height),
+        end,
+        |glyph| match glyph {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        },
+    )?;
+
+    let mut resources = BTreeMap::new();
+    let mut deep_resources = BTreeMap::new();
+    let mut no_go = Vec::new();
+    let mut section = None;
+
+    let position = |line: usize, x: &str, y: &str| -> Result<(usize, usize), MapError> {
+        let (x, y) = (number(line, x)?, number(line, y)?);
+        if x >= width || y >= height {
+            return Err(invalid(line, format!("({}, {}) is outside the map", x, y)));
+        }
+        Ok((x, y))
+    };
+
+    for (line, content) in lines {
+        if content.is_empty() {
+            continue;
+        }
+        if let Some(next) = Section::from_keyword(content) {
+            section = Some(next);
+            continue;
+        }
+
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        match (section, &fields[..]) {
+            (Some(Section::Resources | Section::Deep), &[x, y, resource_type, amount]) => {
+                let resource_type =
+                    ResourceType::from_str(resource_type).map_err(|e| invalid(line, e))?;
+                let deposit = (resource_type, number(line, amount)?);
+                let layer = if section == Some(Section::Deep) {
+                    &mut deep_resources
+                } else {
+                    &mut resources
+                };
+                layer.insert(position(line, x, y)?, deposit);
+            }
+            (Some(Section::NoGo), &[zone]) => {
+                no_go.push((line, Rect::from_str(zone).map_err(|e| invalid(line, e))?));
+            }
+            _ => return Err(invalid(line, format!("unexpected '{}'", content))),
+        }
+    }
+
+    let mut map = Map::from_parts(seed, terrain, resources, deep_resources, discovered)?;
+    for (line, zone) in no_go {
+        map.add_no_go_zone(zone)
+            .map_err(|e| invalid(line, e.to_string()))?;
+    }
+
+    Ok(map)
+}
+
+// `height` run-length encoded rows of exactly `width` cells each. Runs are
+// checked against the room left in the row before they are expanded.
+fn grid<'a, T: Copy>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    name: &str,
+    (width, height): (usize, usize),
+    end: usize,
+    decode: impl Fn(char) -> Option<T>,
+) -> Result<Vec<Vec<T>>, MapError> {
+    let mut rows = Vec::with_capacity(height);
+    for y in 0..height {
+        let (line, text) = lines
+            .next()
+            .ok_or_else(|| invalid(end, format!("{} ends after {} of {} rows", name, y, height)))?;
+        let mut row = Vec::with_capacity(width);
+        let mut count: Option<usize> = None;
+        for glyph in text.chars() {
+            if let Some(digit) = glyph.to_digit(10) {
+                count = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as usize))
+                    .filter(|&count| count <= width);
+                if count.is_none() {
+                    return Err(invalid(
+                        line,
+                        format!("{} row has more than {} cells", name, width),
+                    ));
+                }
+                continue;
+            }
+            let value = decode(glyph)
+                .ok_or_else(|| invalid(line, format!("unknown {} '{}'", name, glyph)))?;
+            let run = count.take().unwrap_or(1);
+            if run == 0 || row.len() + run > width {
+                return Err(invalid(
+                    line,
+                    format!(
+                        "{} row has a run of {} with {} cells left",
+                        name,
+                        run,
+                        width - row.len()
+                    ),
+                ));
+            }
+            row.resize(row.len() + run, value);
+        }
+        if count.is_some() || row.len() != width {
+            return Err(invalid(
+                line,
+                format!("{} row has {} cells, expected {}", name, row.len(), width),
+            ));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn encode_runs(glyphs: impl Iterator<Item = char>) -> String {
+    let mut encoded = String::new();
+    let mut glyphs = glyphs.peekable();
+    while let Some(glyph) = glyphs.next() {
+        let mut run = 1;
+        while glyphs.next_if_eq(&glyph).is_some() {
+            run += 1;
+        }
+        if run > 1 {
+            encoded.push_str(&run.to_string());
+        }
+        encoded.push(glyph);
+    }
+    encoded
+}
+
+// The words after `key` on the next line, or an error naming what was missing.
+fn keyed<'a>(
+    next: Option<(usize, &'a str)>,
+    key: &str,
+    end: usize,
+) -> Result<(usize, Vec<&'a str>), MapError> {
+    let Some((line, content)) = next else {
+        return Err(invalid(end, format!("file ends before '{}'", key)));
+    };
+    let mut words = content.split_whitespace();
+    if words.next() != Some(key) {
+        return Err(invalid(line, format!("expected '{}'", key)));
+    }
+    Ok((line, words.collect()))
+}
+
+fn number<T: FromStr>(line: usize, value: &str) -> Result<T, MapError> {
+    value
+        .parse()
+        .map_err(|_| invalid(line, format!("'{}' is not a valid number", value)))
+}
+
+fn invalid(line: usize, reason: impl Into<String>) -> MapError {
+    MapError::InvalidFile {
+        line,
+        reason: reason.into(),
+    }
+}
+
+fn terrain_glyph(terrain: TerrainType) -> char {
+    match terrain {
+        TerrainType::Plain => '.',
+        TerrainType::Hill => 'n',
+        TerrainType::Canyon => '~',
+        TerrainType::Mountain => '^',
+    }
+}
+
+fn terrain_from_glyph(glyph: char) -> Option<u8> {
+    match glyph {
+        '.' => Some(0),
+        'n' => Some(1),
+        '~' => Some(2),
+        '^' => Some(3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(map: &Map) -> String {
+        let mut out = Vec::new();
+        map.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn error_line(text: &str, limits: MapLimits) -> usize {
+        match parse(text, limits) {
+            Err(MapError::InvalidFile { line, .. }) => line,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("loaded an invalid file"),
+        }
+    }
+
+    #[test]
+    fn saved_maps_load_back_unchanged() {
+        let mut map = Map::new(30, 12, 9).unwrap();
+        map.add_no_go_zone(Rect {
+            x: 2,
+            y: 3,
+            width: 4,
+            height: 2,
+        })
+        .unwrap();
+        let text = saved(&map);
+        let loaded = parse(&text, MapLimits::default()).unwrap();
+        assert_eq!(loaded.terrain, map.terrain);
+        assert_eq!(loaded.no_go_zones(), map.no_go_zones());
+        assert_eq!(saved(&loaded), text);
+    }
+
+    #[test]
+    fn sizes_beyond_the_limits_are_rejected_before_loading() {
+        let text = saved(&Map::new(30, 12, 9).unwrap());
+        let limits = |max_width, max_height| MapLimits {
+            max_width,
+            max_height,
+        };
+        assert!(parse(&text, limits(30, 12)).is_ok());
+        assert_eq!(error_line(&text, limits(29, 12)), 2);
+        assert_eq!(error_line(&text, limits(30, 11)), 2);
+
+        let huge = text.replacen("size 30 12", "size 18446744073709551615 2", 1);
+        assert_eq!(error_line(&huge, MapLimits::default()), 2);
+    }
+
+    #[test]
+    fn contents_must_match_the_declared_size() {
+        let text = saved(&Map::new(30, 12, 9).unwrap());
+        let limits = MapLimits::default();
+
+        let wider = text.replacen("size 30 12", "size 31 12", 1);
+        assert_eq!(error_line(&wider, limits), 5);
+
+        let taller = text.replacen("size 30 12", "size 30 13", 1);
+        assert!(matches!(
+            parse(&taller, limits),
+            Err(MapError::InvalidFile { .. })
+        ));
+
+        let outside = text.replacen("resources\n", "resources\n30 0 mineral 5\n", 1);
+        assert_eq!(error_line(&outside, limits), 31);
+    }
+
+    #[test]
+    fn rows_are_run_length_encoded() {
+        assert_eq!(encode_runs("....n~~^".chars()), "4.n2~^");
+        assert_eq!(encode_runs("".chars()), "");
+
+        let mut lines = [(7, "4.n2~^")].into_iter();
+        let rows = grid(&mut lines, "terrain", (8, 1), 8, terrain_from_glyph).unwrap();
+        assert_eq!(rows, vec![vec![0, 0, 0, 0, 1, 2, 2, 3]]);
+    }
+
+    #[test]
+    fn malformed_runs_are_rejected_without_expanding_them() {
+        for row in ["9.", "18446744073709551616.", "0.8.", "8", "4.4x", "4.3."] {
+            let mut lines = [(3, row)].into_iter();
+            assert!(
+                grid(&mut lines, "terrain", (8, 1), 4, terrain_from_glyph).is_err(),
+                "{}",
+                row
+            );
+        }
+    }
+
+    #[test]
+    fn large_maps_save_compactly_with_their_discoveries() {
+        let mut map = Map::new(256, 128, 3).unwrap();
+        for (y, row) in map.discovered.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = (x / 16 + y / 16) % 3 == 0;
+            }
+        }
+        let text = saved(&map);
+        let grids = text.split("\nresources\n").next().unwrap();
+        // Both grids together take less than half of one glyph per cell.
+        assert!(grids.len() < map.width * map.height / 2);
+
+        let loaded = parse(&text, MapLimits::default()).unwrap();
+        assert_eq!(loaded.discovered, map.discovered);
+        assert_eq!(loaded.terrain, map.terrain);
+        assert_eq!(loaded.resources, map.resources);
+        assert_eq!(loaded.deep_resources, map.deep_resources);
+    }
+
+    // A deterministic stand-in for fuzzing: every truncation of a real save,
+    // and every line with a byte swapped for a troublesome one, must come
+    // back as a result rather than a panic.
+    #[test]
+    fn truncated_or_mutated_saves_never_panic() {
+        let mut map = Map::new(12, 6, 4).unwrap();
+        map.add_no_go_zone(Rect {
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+        })
+        .unwrap();
+        let text = saved(&map);
+        let limits = MapLimits::default();
+
+        for end in 0..text.len() {
+            let _ = parse(&text[..end], limits);
+        }
+        for position in 0..text.len() {
+            for replacement in [b'0', b'9', b' ', b'\n', b'-', b'x', b'^', b'#'] {
+                let mut bytes = text.clone().into_bytes();
+                bytes[position] = replacement;
+                let _ = parse(std::str::from_utf8(&bytes).unwrap(), limits);
+            }
+            let huge = format!(
+                "{}18446744073709551615{}",
+                &text[..position],
+                &text[position..]
+            );
+            let _ = parse(&huge, limits);
+        }
+    }
+
+    #[test]
+    fn limits_parse_from_width_by_height() {
+        assert_eq!(
+            "64x32".parse(),
+            Ok(MapLimits {
+                max_width: 64,
+                max_height: 32
+            })
+        );
+        assert!("0x32".parse::<MapLimits>().is_err());
+        assert!("64".parse::<MapLimits>().is_err());
+    }
+}
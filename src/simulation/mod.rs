@@ -1 +1,11 @@
 pub mod entities;
+pub mod influence;
+pub mod map_file;
+pub mod occupancy;
+pub mod pathfinding;
+pub mod setup;
+pub mod stats;
+#[cfg(test)]
+pub mod testing;
+pub mod view;
+pub mod world;
@@ -0,0 +1,136 @@
+use crate::simulation::entities::{Map, Robot, RobotId, Station};
+
+// Which robot stands on each cell, so no two robots share one. The station
+// is exempt: every robot has to be able to dock there.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct OccupancyGrid {
+    width: usize,
+    station: usize,
+    cells: Vec<Option<RobotId>>,
+}
+
+#[allow(dead_code)]
+impl OccupancyGrid {
+    pub fn new(map: &Map, station: &Station, robots: &[Robot]) -> OccupancyGrid {
+        let mut grid = OccupancyGrid {
+            width: map.width,
+            station: station.y * map.width + station.x,
+            cells: vec![None; map.width * map.height],
+        };
+        for robot in robots {
+            grid.occupy(robot.x, robot.y, robot.id);
+        }
+        grid
+    }
+
+    pub fn occupant(&self, x: usize, y: usize) -> Option<RobotId> {
+        self.cells.get(y * self.width + x).copied().flatten()
+    }
+
+    pub fn is_free(&self, x: usize, y: usize) -> bool {
+        y * self.width + x == self.station || self.occupant(x, y).is_none()
+    }
+
+    // Moves `robot` along `path` like Robot::advance, but waits in front of
+    // any cell another robot holds. Callers resolve a tick by moving robots
+    // in id order, so conflicts always go the same way; a robot that keeps
+    // waiting can re-path around the others with OccupancyPolicy::Avoid.
+    pub fn advance(&mut self, robot: &mut Robot, path: &[(usize, usize)], map: &Map) -> usize {
+        let from = (robot.x, robot.y);
+        let moved = robot.advance_while(path, map, |x, y| self.is_free(x, y));
+
+        if moved > 0 {
+            self.vacate(from.0, from.1);
+            self.occupy(robot.x, robot.y, robot.id);
+        }
+        moved
+    }
+
+    fn occupy(&mut self, x: usize, y: usize, id: RobotId) {
+        let index = y * self.width + x;
+        if index != self.station {
+            self.cells[index] = Some(id);
+        }
+    }
+
+    fn vacate(&mut self, x: usize, y: usize) {
+        self.cells[y * self.width + x] = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::entities::RobotType;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn open_map() -> Map {
+        let (width, height) = (10, 3);
+        Map::from_parts(
+            7,
+            vec![vec![0; width]; height],
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; width]; height],
+        )
+        .unwrap()
+    }
+
+    fn station(x: usize, y: usize) -> Station {
+        Station {
+            resources: HashMap::new(),
+            discoveries: 0,
+            x,
+            y,
+        }
+    }
+
+    fn robot(id: usize, x: usize, y: usize) -> Robot {
+        Robot {
+            id: RobotId(id),
+            robot_type: RobotType::Explorer,
+            x,
+            y,
+            energy: 100,
+            carrying: None,
+            movement_points: 0,
+            movement_cost: 0,
+        }
+    }
+
+    #[test]
+    fn robots_wait_behind_occupied_cells_and_follow_once_free() {
+        let map = open_map();
+        let mut robots = vec![robot(0, 0, 1), robot(1, 2, 1)];
+        let mut grid = OccupancyGrid::new(&map, &station(9, 0), &robots);
+        let path = [(0, 1), (1, 1), (2, 1), (3, 1)];
+
+        let (first, rest) = robots.split_at_mut(1);
+        assert_eq!(grid.advance(&mut first[0], &path, &map), 1);
+        assert_eq!((first[0].x, first[0].y), (1, 1));
+        assert_eq!(grid.occupant(0, 1), None);
+        assert_eq!(grid.occupant(1, 1), Some(RobotId(0)));
+
+        let detour = [(2, 1), (2, 2)];
+        assert_eq!(grid.advance(&mut rest[0], &detour, &map), 1);
+        assert!(grid.is_free(2, 1));
+
+        assert_eq!(grid.advance(&mut first[0], &path, &map), 2);
+        assert_eq!(grid.occupant(3, 1), Some(RobotId(0)));
+    }
+
+    #[test]
+    fn the_station_never_blocks() {
+        let map = open_map();
+        let mut robots = vec![robot(0, 4, 1), robot(1, 6, 1)];
+        let mut grid = OccupancyGrid::new(&map, &station(5, 1), &robots);
+
+        let (first, rest) = robots.split_at_mut(1);
+        assert_eq!(grid.advance(&mut first[0], &[(4, 1), (5, 1)], &map), 1);
+        assert_eq!(grid.advance(&mut rest[0], &[(6, 1), (5, 1)], &map), 1);
+        assert!(grid.is_free(5, 1));
+        assert_eq!(grid.occupant(4, 1), None);
+        assert_eq!(grid.occupant(6, 1), None);
+    }
+}
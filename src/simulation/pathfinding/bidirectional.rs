@@ -0,0 +1,212 @@
+use super::{MovementMode, Path, Pathfinding, Position, UNVISITED};
+use crate::simulation::entities::Map;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+struct Frontier {
+    best: Vec<u32>,
+    link: Vec<usize>,
+    closed: Vec<bool>,
+    open: BinaryHeap<Reverse<(i64, Reverse<u32>, usize)>>,
+}
+
+impl Frontier {
+    fn new(cells: usize, origin: usize, key: i64) -> Frontier {
+        let mut frontier = Frontier {
+            best: vec![u32::MAX; cells],
+            link: vec![UNVISITED; cells],
+            closed: vec![false; cells],
+            open: BinaryHeap::new(),
+        };
+        frontier.best[origin] = 0;
+        frontier.open.push(Reverse((key, Reverse(0), origin)));
+        frontier
+    }
+
+    fn min_key(&self) -> Option<i64> {
+        self.open.peek().map(|Reverse((key, _, _))| *key)
+    }
+
+    fn pop(&mut self) -> Option<(u32, usize)> {
+        while let Some(Reverse((_, Reverse(cost), index))) = self.open.pop() {
+            if !self.closed[index] && cost == self.best[index] {
+                self.closed[index] = true;
+                return Some((cost, index));
+            }
+        }
+        None
+    }
+}
+
+// A* run from both ends at once with the averaged potential
+// (h_goal - h_start) / 2, which keeps both directions consistent so the
+// frontiers can stop as soon as their keys sum past the best meeting point.
+// Keys are doubled to stay integral and ties go to the deeper node. The
+// backward search walks edges in reverse, so stepping from a cell to its
+// predecessor costs entering the cell itself.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct BidirectionalPathfinder;
+
+#[allow(dead_code)]
+impl BidirectionalPathfinder {
+    pub fn new() -> BidirectionalPathfinder {
+        BidirectionalPathfinder
+    }
+}
+
+impl Pathfinding for BidirectionalPathfinder {
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        if !map.in_bounds(start.0, start.1) || !map.in_bounds(goal.0, goal.1) {
+            return None;
+        }
+        map.movement_cost(goal.0, goal.1)?;
+        if !map.connected(start, goal) {
+            return None;
+        }
+
+        let cells = map.width * map.height;
+        let index = |(x, y): Position| y * map.width + x;
+        let position = |i: usize| (i % map.width, i / map.width);
+
+        let heuristic = |from, to| MovementMode::Cardinal.heuristic(from, to) as i64;
+        let potential = |p: Position| heuristic(p, goal) - heuristic(p, start);
+
+        let (start_index, goal_index) = (index(start), index(goal));
+        let mut forward = Frontier::new(cells, start_index, potential(start));
+        let mut backward = Frontier::new(cells, goal_index, -potential(goal));
+
+        let mut best_total = if start == goal { Some(0) } else { None };
+        let mut meeting = start_index;
+
+        while let (Some(forward_key), Some(backward_key)) = (forward.min_key(), backward.min_key())
+        {
+            if best_total.is_some_and(|total| forward_key + backward_key >= 2 * total as i64) {
+                break;
+            }
+
+            let expand_forward = forward.open.len() <= backward.open.len();
+            let (frontier, other) = if expand_forward {
+                (&mut forward, &backward)
+            } else {
+                (&mut backward, &forward)
+            };
+            let Some((cost, current)) = frontier.pop() else {
+                continue;
+            };
+
+            let (x, y) = position(current);
+            for next in map.neighbors(x, y) {
+                let next_index = index(next);
+                let step = if expand_forward {
+                    map.movement_cost(next.0, next.1)
+                } else if next_index == start_index || map.movement_cost(next.0, next.1).is_some() {
+                    map.movement_cost(x, y)
+                } else {
+                    None
+                };
+                let Some(step) = step else {
+                    continue;
+                };
+
+                let next_cost = cost + step;
+                if next_cost < frontier.best[next_index] {
+                    frontier.best[next_index] = next_cost;
+                    frontier.link[next_index] = current;
+                    let bias = if expand_forward {
+                        potential(next)
+                    } else {
+                        -potential(next)
+                    };
+                    frontier.open.push(Reverse((
+                        2 * next_cost as i64 + bias,
+                        Reverse(next_cost),
+                        next_index,
+                    )));
+
+                    if other.best[next_index] != u32::MAX {
+                        let total = next_cost + other.best[next_index];
+                        if best_total.is_none_or(|best| total < best) {
+                            best_total = Some(total);
+                            meeting = next_index;
+                        }
+                    }
+                }
+            }
+        }
+
+        let cost = best_total?;
+
+        let mut positions = Vec::new();
+        let mut current = meeting;
+        while current != UNVISITED {
+            positions.push(position(current));
+            current = forward.link[current];
+        }
+        positions.reverse();
+
+        let mut current = backward.link[meeting];
+        while current != UNVISITED {
+            positions.push(position(current));
+            current = backward.link[current];
+        }
+
+        Some(Path {
+            positions,
+            cost,
+            complete: true,
+        })
+    }
+
+    fn is_exact(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::entities::MapConstants;
+    use crate::simulation::pathfinding::Pathfinder;
+    use crate::simulation::pathfinding::testing::{query_pairs, walked_cost};
+
+    fn assert_matches_astar(map: &Map) {
+        let (astar, bidirectional) = (Pathfinder::new(), BidirectionalPathfinder::new());
+        for (start, goal) in query_pairs(map, 80) {
+            let expected = astar.find_path(start, goal, map).map(|path| path.cost);
+            let found = bidirectional.find_path(start, goal, map);
+
+            assert_eq!(
+                found.as_ref().map(|path| path.cost),
+                expected,
+                "{:?} -> {:?}",
+                start,
+                goal
+            );
+            if let Some(path) = found {
+                assert_eq!(path.positions.first(), Some(&start));
+                assert_eq!(path.positions.last(), Some(&goal));
+                assert_eq!(walked_cost(map, &path), Some(path.cost));
+            }
+        }
+    }
+
+    #[test]
+    fn costs_match_astar() {
+        for seed in 0..20 {
+            assert_matches_astar(&Map::new(40, 24, seed).unwrap());
+        }
+    }
+
+    #[test]
+    fn costs_match_astar_on_rough_terrain() {
+        let constants = MapConstants {
+            obstacle_density: 0.8,
+            ..MapConstants::default()
+        };
+        for seed in 0..10 {
+            let map = Map::with_progress(40, 24, seed, &constants, |_, _, _| {}).unwrap();
+            assert_matches_astar(&map);
+        }
+    }
+}
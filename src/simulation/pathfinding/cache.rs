@@ -0,0 +1,321 @@
+use super::{Path, Pathfinding, Position};
+use crate::simulation::entities::Map;
+use std::cell::{RefCell, RefMut};
+use std::collections::{HashMap, VecDeque};
+
+const REGION_SIZE: usize = 8;
+const MAX_ROUTES: usize = 64;
+const PATHS_PER_ROUTE: usize = 4;
+
+type Region = (usize, usize);
+type Route = (Region, Region);
+
+struct CachedPath {
+    path: Path,
+    steps: HashMap<Position, usize>,
+}
+
+impl CachedPath {
+    fn new(path: Path) -> CachedPath {
+        let steps = path
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(step, &position)| (position, step))
+            .collect();
+        CachedPath { path, steps }
+    }
+
+    fn whole(&self, start: Position, goal: Position) -> Option<Path> {
+        let positions = &self.path.positions;
+        (positions.first() == Some(&start) && positions.last() == Some(&goal))
+            .then(|| self.path.clone())
+    }
+
+    // Every stretch of an optimal path is itself optimal, so any trip whose
+    // ends both lie on the path, in order, can be answered from it.
+    fn segment(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        let (&from, &to) = (self.steps.get(&start)?, self.steps.get(&goal)?);
+        if from > to {
+            return None;
+        }
+        let positions = self.path.positions[from..=to].to_vec();
+        let cost = positions[1..]
+            .iter()
+            .map(|&(x, y)| map.movement_cost(x, y))
+            .sum::<Option<u32>>()?;
+
+        Some(Path {
+            positions,
+            cost,
+            complete: true,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    // Seed and terrain version of the map the cached paths were found on.
+    stamp: Option<(u64, u64)>,
+    routes: HashMap<Route, VecDeque<CachedPath>>,
+    // Least recently used route first.
+    order: VecDeque<Route>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Cache {
+    fn touch(&mut self, route: Route) {
+        if let Some(at) = self.order.iter().position(|&r| r == route) {
+            self.order.remove(at);
+        }
+        self.order.push_back(route);
+    }
+
+    fn insert(&mut self, route: Route, path: Path) {
+        let paths = self.routes.entry(route).or_default();
+        paths.push_front(CachedPath::new(path));
+        paths.truncate(PATHS_PER_ROUTE);
+        self.touch(route);
+
+        while self.order.len() > MAX_ROUTES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.routes.remove(&oldest);
+            }
+        }
+    }
+}
+
+// Remembers recent complete paths per pair of coarse map regions, so robots
+// shuttling between the same vein and the station reuse the first trip
+// instead of searching again. Stretches of a cached path are only reused when
+// the inner pathfinder is exact; a stretch of a weighted or hierarchical path
+// can be far from the cheapest. Partial paths are never cached, and
+// everything is dropped as soon as the terrain version changes.
+#[allow(dead_code)]
+pub struct CachedPathfinder {
+    inner: Box<dyn Pathfinding>,
+    cache: RefCell<Cache>,
+}
+
+#[allow(dead_code)]
+impl CachedPathfinder {
+    pub fn new(inner: Box<dyn Pathfinding>) -> CachedPathfinder {
+        CachedPathfinder {
+            inner,
+            cache: RefCell::new(Cache::default()),
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.cache.borrow().hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.cache.borrow().misses
+    }
+
+    pub fn clear(&self) {
+        *self.cache.borrow_mut() = Cache::default();
+    }
+
+    // The cache, emptied first if it holds paths from another map or an
+    // older version of this one.
+    fn synced(&self, map: &Map) -> RefMut<'_, Cache> {
+        let stamp = (map.seed(), map.terrain_version());
+        let mut cache = self.cache.borrow_mut();
+        if cache.stamp != Some(stamp) {
+            cache.stamp = Some(stamp);
+            cache.routes.clear();
+            cache.order.clear();
+        }
+        cache
+    }
+}
+
+impl Pathfinding for CachedPathfinder {
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        let route = (region(start), region(goal));
+        let mut cache = self.synced(map);
+
+        // The route's own paths are the likeliest match, but a trip can also
+        // be a stretch of a longer one cached under another route. Those are
+        // tried most recently used first, so the answer never depends on
+        // hash order.
+        let exact = self.inner.is_exact();
+        let hit = cache
+            .routes
+            .get(&route)
+            .into_iter()
+            .chain(
+                cache
+                    .order
+                    .iter()
+                    .rev()
+                    .filter_map(|other| cache.routes.get(other)),
+            )
+            .flatten()
+            .find_map(|cached| {
+                if exact {
+                    cached.segment(start, goal, map)
+                } else {
+                    cached.whole(start, goal)
+                }
+            });
+        if let Some(path) = hit {
+            cache.hits += 1;
+            if cache.routes.contains_key(&route) {
+                cache.touch(route);
+            }
+            return Some(path);
+        }
+        cache.misses += 1;
+
+        let path = self.inner.find_path(start, goal, map)?;
+        if path.complete {
+            cache.insert(route, path.clone());
+        }
+        Some(path)
+    }
+
+    // Goes straight to the inner pathfinder, which may answer with a single
+    // search, and keeps the result like any other trip.
+    fn find_path_to_any(&self, start: Position, goals: &[Position], map: &Map) -> Option<Path> {
+        let path = self.inner.find_path_to_any(start, goals, map)?;
+        if path.complete {
+            let goal = *path.positions.last().expect("paths include the start");
+            self.synced(map)
+                .insert((region(start), region(goal)), path.clone());
+        }
+        Some(path)
+    }
+
+    fn is_exact(&self) -> bool {
+        self.inner.is_exact()
+    }
+}
+
+fn region((x, y): Position) -> Region {
+    (x / REGION_SIZE, y / REGION_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::pathfinding::Pathfinder;
+    use crate::simulation::pathfinding::testing::query_pairs;
+    use std::collections::BTreeMap;
+
+    // Answers from a fixed set of paths, so tests can choose what is cached.
+    struct Scripted(Vec<Path>);
+
+    impl Pathfinding for Scripted {
+        fn find_path(&self, start: Position, goal: Position, _: &Map) -> Option<Path> {
+            self.0
+                .iter()
+                .find(|path| {
+                    path.positions.first() == Some(&start) && path.positions.last() == Some(&goal)
+                })
+                .cloned()
+        }
+
+        fn is_exact(&self) -> bool {
+            true
+        }
+    }
+
+    fn walk(positions: Vec<Position>) -> Path {
+        Path {
+            cost: positions.len() as u32 - 1,
+            positions,
+            complete: true,
+        }
+    }
+
+    fn long_trip(map: &Map) -> (Position, Position) {
+        query_pairs(map, 50)
+            .into_iter()
+            .find(|&(start, goal)| {
+                Pathfinder::new()
+                    .find_path(start, goal, map)
+                    .is_some_and(|path| path.complete && path.positions.len() >= 12)
+            })
+            .expect("no long trip on the map")
+    }
+
+    #[test]
+    fn stretches_of_exact_paths_are_reused() {
+        let map = Map::new(40, 24, 3).unwrap();
+        let cached = CachedPathfinder::new(Box::new(Pathfinder::new()));
+        let (start, goal) = long_trip(&map);
+
+        let path = cached.find_path(start, goal, &map).unwrap();
+        let (from, to) = (path.positions[2], path.positions[path.positions.len() - 3]);
+        let stretch = cached.find_path(from, to, &map).unwrap();
+
+        assert_eq!(cached.hits(), 1);
+        assert_eq!(
+            Some(stretch.cost),
+            Pathfinder::new().estimate_cost(from, to, &map)
+        );
+    }
+
+    #[test]
+    fn only_whole_trips_are_reused_from_inexact_pathfinders() {
+        let map = Map::new(40, 24, 3).unwrap();
+        let weighted = || Pathfinder::new().with_heuristic_weight(3.0);
+        let cached = CachedPathfinder::new(Box::new(weighted()));
+        assert!(!cached.is_exact());
+        let (start, goal) = long_trip(&map);
+
+        let path = cached.find_path(start, goal, &map).unwrap();
+        let (from, to) = (path.positions[2], path.positions[path.positions.len() - 3]);
+        let stretch = cached.find_path(from, to, &map).unwrap();
+        assert_eq!((cached.hits(), cached.misses()), (0, 2));
+        assert_eq!(stretch, weighted().find_path(from, to, &map).unwrap());
+
+        assert_eq!(cached.find_path(start, goal, &map), Some(path));
+        assert_eq!(cached.hits(), 1);
+    }
+
+    #[test]
+    fn overlapping_paths_are_tried_most_recent_first() {
+        let (width, height) = (16, 12);
+        let map = Map::from_parts(
+            0,
+            vec![vec![0; width]; height],
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; width]; height],
+        )
+        .unwrap();
+
+        // Both cover (1, 1) -> (3, 1): one straight along the row, the other
+        // with a detour through the row above.
+        let straight = walk((0..=10).map(|x| (x, 1)).collect());
+        let detour = walk(
+            (1..=9)
+                .rev()
+                .map(|y| (1, y))
+                .chain([(1, 0), (2, 0), (3, 0)])
+                .chain((1..=10).map(|x| (x, 1)).skip(2))
+                .collect(),
+        );
+        let cached =
+            CachedPathfinder::new(Box::new(Scripted(vec![straight.clone(), detour.clone()])));
+        let ends = |path: &Path| (path.positions[0], *path.positions.last().unwrap());
+
+        let (start, goal) = ends(&straight);
+        cached.find_path(start, goal, &map).unwrap();
+        let (start, goal) = ends(&detour);
+        cached.find_path(start, goal, &map).unwrap();
+        let stretch = cached.find_path((1, 1), (3, 1), &map).unwrap();
+        assert_eq!(stretch.positions, [(1, 1), (1, 0), (2, 0), (3, 0), (3, 1)]);
+
+        let (start, goal) = ends(&straight);
+        cached.find_path(start, goal, &map).unwrap();
+        let stretch = cached.find_path((1, 1), (3, 1), &map).unwrap();
+        assert_eq!(stretch.positions, [(1, 1), (2, 1), (3, 1)]);
+        assert_eq!(stretch.cost, 2);
+    }
+}
@@ -0,0 +1,118 @@
+use super::{MovementMode, Position};
+use crate::simulation::entities::Map;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Cost of the cheapest path from every cell to a fixed target, built with one
+// reverse Dijkstra pass, matching what A* with the same movement mode would
+// charge for the same trip. Diagonal adjacency is symmetric, so walking the
+// neighbors backwards is safe in either mode.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    width: usize,
+    target: Position,
+    costs: Vec<u32>,
+}
+
+#[allow(dead_code)]
+impl DistanceField {
+    pub fn new(map: &Map, target: Position) -> DistanceField {
+        Self::with_movement(map, target, MovementMode::Cardinal)
+    }
+
+    pub fn with_movement(map: &Map, target: Position, movement: MovementMode) -> DistanceField {
+        let index = |(x, y): Position| y * map.width + x;
+        let mut costs = vec![u32::MAX; map.width * map.height];
+        let mut open = BinaryHeap::new();
+
+        if map.in_bounds(target.0, target.1) {
+            costs[index(target)] = 0;
+            open.push(Reverse((0, target)));
+        }
+
+        while let Some(Reverse((cost, current))) = open.pop() {
+            if cost > costs[index(current)] {
+                continue;
+            }
+            let Some(step) = map.movement_cost(current.0, current.1) else {
+                continue;
+            };
+
+            for next in movement.neighbors(map, current) {
+                if map.movement_cost(next.0, next.1).is_none() {
+                    continue;
+                }
+                let next_cost = cost + step;
+                if next_cost < costs[index(next)] {
+                    costs[index(next)] = next_cost;
+                    open.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        DistanceField {
+            width: map.width,
+            target,
+            costs,
+        }
+    }
+
+    pub fn target(&self) -> Position {
+        self.target
+    }
+
+    pub fn cost_at(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width {
+            return None;
+        }
+        self.costs
+            .get(y * self.width + x)
+            .copied()
+            .filter(|&cost| cost != u32::MAX)
+    }
+
+    pub fn can_reach(&self, x: usize, y: usize, energy: u32) -> bool {
+        self.cost_at(x, y).is_some_and(|cost| cost <= energy)
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.costs.len() * std::mem::size_of::<u32>()
+    }
+
+    pub fn max_cost(&self) -> Option<u32> {
+        self.costs.iter().copied().filter(|&c| c != u32::MAX).max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::pathfinding::testing::open_cells;
+    use crate::simulation::pathfinding::{Pathfinder, Pathfinding};
+
+    #[test]
+    fn costs_match_astar_in_both_movement_modes() {
+        for movement in [MovementMode::Cardinal, MovementMode::Diagonal] {
+            let astar = Pathfinder::new().with_movement(movement);
+            for seed in 0..5 {
+                let map = Map::new(32, 16, seed).unwrap();
+                let cells = open_cells(&map);
+                let target = cells[cells.len() / 2];
+                let field = DistanceField::with_movement(&map, target, movement);
+
+                for &(x, y) in &cells {
+                    assert_eq!(
+                        field.cost_at(x, y),
+                        astar.estimate_cost((x, y), target, &map),
+                        "{} movement from ({}, {}) on seed {}",
+                        movement,
+                        x,
+                        y,
+                        seed
+                    );
+                }
+            }
+        }
+    }
+}
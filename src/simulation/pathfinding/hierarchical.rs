@@ -0,0 +1,538 @@
+use super::{MovementMode, Path, Pathfinding, Position, UNVISITED};
+use crate::simulation::entities::Map;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const DEFAULT_CLUSTER_SIZE: usize = 16;
+// Entrances at least this wide get a portal at each end instead of one in
+// the middle, which keeps refined paths from zig-zagging through a single
+// doorway on long open borders.
+const WIDE_ENTRANCE: usize = 6;
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+impl Bounds {
+    fn width(&self) -> usize {
+        self.x1 - self.x0
+    }
+
+    fn contains(&self, (x, y): Position) -> bool {
+        (self.x0..self.x1).contains(&x) && (self.y0..self.y1).contains(&y)
+    }
+
+    fn index(&self, (x, y): Position) -> usize {
+        (y - self.y0) * self.width() + (x - self.x0)
+    }
+
+    fn position(&self, index: usize) -> Position {
+        (
+            self.x0 + index % self.width(),
+            self.y0 + index / self.width(),
+        )
+    }
+}
+
+struct LocalSearch {
+    bounds: Bounds,
+    cost: Vec<u32>,
+    came_from: Vec<usize>,
+}
+
+impl LocalSearch {
+    fn cost_to(&self, position: Position) -> Option<u32> {
+        let cost = self.cost[self.bounds.index(position)];
+        (cost != u32::MAX).then_some(cost)
+    }
+
+    fn path_to(&self, position: Position) -> Vec<Position> {
+        let mut path = Vec::new();
+        let mut current = self.bounds.index(position);
+        while current != UNVISITED {
+            path.push(self.bounds.position(current));
+            current = self.came_from[current];
+        }
+        path.reverse();
+        path
+    }
+}
+
+// Precomputed abstract graph over cluster border portals. Answers long
+// queries by searching the small portal graph and refining each hop inside
+// a single cluster. The graph is rebuilt on the first query after the
+// terrain changes.
+#[allow(dead_code)]
+pub struct HierarchicalPathfinder {
+    graph: RefCell<PortalGraph>,
+}
+
+struct PortalGraph {
+    // Seed and terrain version of the map the graph was built from.
+    stamp: (u64, u64),
+    cluster_size: usize,
+    nodes: Vec<Position>,
+    node_ids: HashMap<Position, usize>,
+    edges: Vec<Vec<(usize, u32)>>,
+    cluster_nodes: HashMap<(usize, usize), Vec<usize>>,
+}
+
+#[allow(dead_code)]
+impl HierarchicalPathfinder {
+    pub fn new(map: &Map) -> HierarchicalPathfinder {
+        Self::with_cluster_size(map, DEFAULT_CLUSTER_SIZE)
+    }
+
+    pub fn with_cluster_size(map: &Map, cluster_size: usize) -> HierarchicalPathfinder {
+        HierarchicalPathfinder {
+            graph: RefCell::new(PortalGraph::build(map, cluster_size.max(2))),
+        }
+    }
+
+    pub fn portal_count(&self) -> usize {
+        self.graph.borrow().nodes.len()
+    }
+}
+
+impl Pathfinding for HierarchicalPathfinder {
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        if self.graph.borrow().stamp != (map.seed(), map.terrain_version()) {
+            let cluster_size = self.graph.borrow().cluster_size;
+            *self.graph.borrow_mut() = PortalGraph::build(map, cluster_size);
+        }
+        self.graph.borrow().find_path(start, goal, map)
+    }
+}
+
+impl PortalGraph {
+    fn build(map: &Map, cluster_size: usize) -> PortalGraph {
+        let mut graph = PortalGraph {
+            stamp: (map.seed(), map.terrain_version()),
+            cluster_size,
+            nodes: Vec::new(),
+            node_ids: HashMap::new(),
+            edges: Vec::new(),
+            cluster_nodes: HashMap::new(),
+        };
+
+        graph.build_entrances(map);
+        graph.build_intra_edges(map);
+        graph
+    }
+
+    fn abstract_search(
+        &self,
+        start: Position,
+        goal: Position,
+        start_edges: &[(usize, u32)],
+        goal_edges: &HashMap<usize, u32>,
+    ) -> Option<(u32, Vec<usize>)> {
+        let start_id = self.nodes.len();
+        let goal_id = start_id + 1;
+        let position = |id: usize| match id {
+            id if id == start_id => start,
+            id if id == goal_id => goal,
+            id => self.nodes[id],
+        };
+
+        let mut best = vec![u32::MAX; goal_id + 1];
+        let mut came_from = vec![UNVISITED; goal_id + 1];
+        let mut open = BinaryHeap::new();
+
+        best[start_id] = 0;
+        open.push(Reverse((
+            MovementMode::Cardinal.heuristic(start, goal),
+            0,
+            start_id,
+        )));
+
+        while let Some(Reverse((_, cost, current))) = open.pop() {
+            if current == goal_id {
+                let mut hops = Vec::new();
+                let mut node = came_from[goal_id];
+                while node != start_id {
+                    hops.push(node);
+                    node = came_from[node];
+                }
+                hops.reverse();
+                return Some((cost, hops));
+            }
+            if cost > best[current] {
+                continue;
+            }
+
+            let edges = if current == start_id {
+                start_edges
+            } else {
+                &self.edges[current]
+            };
+            let to_goal = goal_edges.get(&current).map(|&step| (goal_id, step));
+
+            for (next, step) in edges.iter().copied().chain(to_goal) {
+                let next_cost = cost + step;
+                if next_cost < best[next] {
+                    best[next] = next_cost;
+                    came_from[next] = current;
+                    open.push(Reverse((
+                        next_cost + MovementMode::Cardinal.heuristic(position(next), goal),
+                        next_cost,
+                        next,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn refine(&self, map: &Map, start: Position, goal: Position, hops: &[usize]) -> Vec<Position> {
+        let waypoints: Vec<Position> = std::iter::once(start)
+            .chain(hops.iter().map(|&node| self.nodes[node]))
+            .chain(std::iter::once(goal))
+            .collect();
+
+        let mut positions = vec![start];
+        for pair in waypoints.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let cluster = self.cluster_of(from);
+
+            if cluster == self.cluster_of(to) {
+                let search = local_search(map, self.cluster_bounds(map, cluster), from);
+                positions.extend(search.path_to(to).into_iter().skip(1));
+            } else {
+                positions.push(to);
+            }
+        }
+
+        positions
+    }
+
+    fn cluster_of(&self, (x, y): Position) -> (usize, usize) {
+        (x / self.cluster_size, y / self.cluster_size)
+    }
+
+    fn cluster_bounds(&self, map: &Map, (cx, cy): (usize, usize)) -> Bounds {
+        Bounds {
+            x0: cx * self.cluster_size,
+            y0: cy * self.cluster_size,
+            x1: ((cx + 1) * self.cluster_size).min(map.width),
+            y1: ((cy + 1) * self.cluster_size).min(map.height),
+        }
+    }
+
+    fn nodes_in(&self, cluster: (usize, usize)) -> &[usize] {
+        self.cluster_nodes
+            .get(&cluster)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn node(&mut self, position: Position) -> usize {
+        if let Some(&id) = self.node_ids.get(&position) {
+            return id;
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(position);
+        self.node_ids.insert(position, id);
+        self.edges.push(Vec::new());
+        self.cluster_nodes
+            .entry(self.cluster_of(position))
+            .or_default()
+            .push(id);
+        id
+    }
+
+    fn build_entrances(&mut self, map: &Map) {
+        let size = self.cluster_size;
+
+        // Vertical borders between horizontally adjacent clusters.
+        for x in (size..map.width).step_by(size) {
+            for y0 in (0..map.height).step_by(size) {
+                let y1 = (y0 + size).min(map.height);
+                let cells: Vec<(Position, Position)> =
+                    (y0..y1).map(|y| ((x - 1, y), (x, y))).collect();
+                self.add_entrances(map, &cells);
+            }
+        }
+
+        // Horizontal borders between vertically adjacent clusters.
+        for y in (size..map.height).step_by(size) {
+            for x0 in (0..map.width).step_by(size) {
+                let x1 = (x0 + size).min(map.width);
+                let cells: Vec<(Position, Position)> =
+                    (x0..x1).map(|x| ((x, y - 1), (x, y))).collect();
+                self.add_entrances(map, &cells);
+            }
+        }
+    }
+
+    fn add_entrances(&mut self, map: &Map, border: &[(Position, Position)]) {
+        let open = |&(a, b): &(Position, Position)| {
+            map.movement_cost(a.0, a.1).is_some() && map.movement_cost(b.0, b.1).is_some()
+        };
+
+        let mut runs = Vec::new();
+        let mut run_start = None;
+        for (i, pair) in border.iter().enumerate() {
+            match (open(pair), run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    runs.push(start..i);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = run_start {
+            runs.push(start..border.len());
+        }
+
+        for run in runs {
+            let doors = if run.len() >= WIDE_ENTRANCE {
+                vec![run.start, run.end - 1]
+            } else {
+                vec![run.start + run.len() / 2]
+            };
+
+            for door in doors {
+                let (a, b) = border[door];
+                let (a_id, b_id) = (self.node(a), self.node(b));
+                let into_b = map.movement_cost(b.0, b.1).unwrap_or(u32::MAX);
+                let into_a = map.movement_cost(a.0, a.1).unwrap_or(u32::MAX);
+                self.edges[a_id].push((b_id, into_b));
+                self.edges[b_id].push((a_id, into_a));
+            }
+        }
+    }
+
+    fn build_intra_edges(&mut self, map: &Map) {
+        let mut clusters: Vec<((usize, usize), Vec<usize>)> = self
+            .cluster_nodes
+            .iter()
+            .map(|(&cluster, nodes)| (cluster, nodes.clone()))
+            .collect();
+        clusters.sort_by_key(|&(cluster, _)| cluster);
+
+        for (cluster, nodes) in clusters {
+            let bounds = self.cluster_bounds(map, cluster);
+            for &from in &nodes {
+                let search = local_search(map, bounds, self.nodes[from]);
+                for &to in &nodes {
+                    if to == from {
+                        continue;
+                    }
+                    if let Some(cost) = search.cost_to(self.nodes[to]) {
+                        self.edges[from].push((to, cost));
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        if !map.in_bounds(start.0, start.1) || !map.in_bounds(goal.0, goal.1) {
+            return None;
+        }
+        let goal_step = map.movement_cost(goal.0, goal.1)?;
+        if !map.connected(start, goal) {
+            return None;
+        }
+        if start == goal {
+            return Some(Path {
+                positions: vec![start],
+                cost: 0,
+                complete: true,
+            });
+        }
+
+        let start_cluster = self.cluster_of(start);
+        let goal_cluster = self.cluster_of(goal);
+        let from_start = local_search(map, self.cluster_bounds(map, start_cluster), start);
+        let from_goal = local_search(map, self.cluster_bounds(map, goal_cluster), goal);
+
+        let direct = (start_cluster == goal_cluster)
+            .then(|| from_start.cost_to(goal))
+            .flatten();
+
+        let start_edges: Vec<(usize, u32)> = self
+            .nodes_in(start_cluster)
+            .iter()
+            .filter_map(|&node| Some((node, from_start.cost_to(self.nodes[node])?)))
+            .collect();
+        // A path from a portal to the goal costs the same as the reverse one,
+        // minus entering the portal plus entering the goal.
+        let goal_edges: HashMap<usize, u32> = self
+            .nodes_in(goal_cluster)
+            .iter()
+            .filter_map(|&node| {
+                let position = self.nodes[node];
+                let back = from_goal.cost_to(position)?;
+                let portal_step = map.movement_cost(position.0, position.1)?;
+                Some((node, back + goal_step - portal_step))
+            })
+            .collect();
+
+        let abstract_path = self.abstract_search(start, goal, &start_edges, &goal_edges);
+
+        match (direct, abstract_path) {
+            (Some(cost), Some((abstract_cost, _))) if cost <= abstract_cost => Some(Path {
+                positions: from_start.path_to(goal),
+                cost,
+                complete: true,
+            }),
+            (Some(cost), None) => Some(Path {
+                positions: from_start.path_to(goal),
+                cost,
+                complete: true,
+            }),
+            (_, Some((cost, hops))) => Some(Path {
+                positions: self.refine(map, start, goal, &hops),
+                cost,
+                complete: true,
+            }),
+            (None, None) => None,
+        }
+    }
+}
+
+fn local_search(map: &Map, bounds: Bounds, start: Position) -> LocalSearch {
+    let cells = bounds.width() * (bounds.y1 - bounds.y0);
+    let mut cost = vec![u32::MAX; cells];
+    let mut came_from = vec![UNVISITED; cells];
+    let mut open = BinaryHeap::new();
+
+    cost[bounds.index(start)] = 0;
+    open.push(Reverse((0, bounds.index(start))));
+
+    while let Some(Reverse((current_cost, current))) = open.pop() {
+        if current_cost > cost[current] {
+            continue;
+        }
+
+        let (x, y) = bounds.position(current);
+        for next in map.neighbors(x, y) {
+            if !bounds.contains(next) {
+                continue;
+            }
+            let Some(step) = map.movement_cost(next.0, next.1) else {
+                continue;
+            };
+
+            let next_index = bounds.index(next);
+            if current_cost + step < cost[next_index] {
+                cost[next_index] = current_cost + step;
+                came_from[next_index] = current;
+                open.push(Reverse((current_cost + step, next_index)));
+            }
+        }
+    }
+
+    LocalSearch {
+        bounds,
+        cost,
+        came_from,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::pathfinding::Pathfinder;
+    use crate::simulation::pathfinding::testing::{query_pairs, walked_cost};
+    use crate::simulation::view::Rect;
+
+    fn check_against_astar(
+        map: &Map,
+        hierarchical: &HierarchicalPathfinder,
+        start: Position,
+        goal: Position,
+    ) {
+        let optimal = Pathfinder::new().find_path(start, goal, map);
+        let found = hierarchical.find_path(start, goal, map);
+
+        match (optimal, found) {
+            (None, None) => {}
+            (Some(optimal), Some(found)) => {
+                assert_eq!(found.positions.first(), Some(&start));
+                assert_eq!(found.positions.last(), Some(&goal));
+                assert_eq!(walked_cost(map, &found), Some(found.cost));
+                assert!(
+                    found.cost >= optimal.cost,
+                    "{:?} -> {:?} beat A*: {} < {}",
+                    start,
+                    goal,
+                    found.cost,
+                    optimal.cost
+                );
+            }
+            (optimal, found) => panic!(
+                "{:?} -> {:?}: A* found {:?}, hierarchical found {:?}",
+                start,
+                goal,
+                optimal.map(|p| p.cost),
+                found.map(|p| p.cost)
+            ),
+        }
+    }
+
+    #[test]
+    fn paths_are_valid_and_never_cheaper_than_astar() {
+        for seed in 0..20 {
+            let map = Map::new(40, 24, seed).unwrap();
+            let hierarchical = HierarchicalPathfinder::with_cluster_size(&map, 6);
+            for (start, goal) in query_pairs(&map, 60) {
+                check_against_astar(&map, &hierarchical, start, goal);
+            }
+        }
+    }
+
+    #[test]
+    fn the_graph_follows_terrain_changes() {
+        let mut map = Map::new(40, 24, 4).unwrap();
+        let hierarchical = HierarchicalPathfinder::with_cluster_size(&map, 6);
+        let (start, goal) = query_pairs(&map, 60)
+            .into_iter()
+            .find(|&(start, goal)| {
+                hierarchical
+                    .find_path(start, goal, &map)
+                    .is_some_and(|path| path.positions.len() >= 8)
+            })
+            .unwrap();
+
+        // Block a cell in the middle of the current route; the next query
+        // must route around it rather than through the stale graph.
+        let path = hierarchical.find_path(start, goal, &map).unwrap();
+        let (x, y) = path.positions[path.positions.len() / 2];
+        map.add_no_go_zone(Rect {
+            x,
+            y,
+            width: 1,
+            height: 1,
+        })
+        .unwrap();
+
+        check_against_astar(&map, &hierarchical, start, goal);
+        if let Some(detour) = hierarchical.find_path(start, goal, &map) {
+            assert!(!detour.positions.contains(&(x, y)));
+        }
+    }
+
+    #[test]
+    fn goals_on_portals_are_answered() {
+        for seed in 0..10 {
+            let map = Map::new(40, 24, seed).unwrap();
+            let hierarchical = HierarchicalPathfinder::with_cluster_size(&map, 6);
+            let start = query_pairs(&map, 1)[0].0;
+            let portals = hierarchical.graph.borrow().nodes.clone();
+            for portal in portals {
+                check_against_astar(&map, &hierarchical, start, portal);
+            }
+        }
+    }
+}
@@ -0,0 +1,643 @@
+pub mod bidirectional;
+pub mod cache;
+pub mod distance_field;
+pub mod hierarchical;
+
+use crate::config::PathfindingConfig;
+use crate::simulation::entities::{Map, Robot};
+use bidirectional::BidirectionalPathfinder;
+use cache::CachedPathfinder;
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
+use hierarchical::HierarchicalPathfinder;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+
+pub type Position = (usize, usize);
+
+const UNVISITED: usize = usize::MAX;
+
+struct Search {
+    cost: u32,
+    end: usize,
+    complete: bool,
+    came_from: Vec<usize>,
+}
+
+impl Search {
+    fn into_path(self, map: &Map) -> Path {
+        let mut positions = Vec::new();
+        let mut current = self.end;
+        while current != UNVISITED {
+            positions.push((current % map.width, current / map.width));
+            current = self.came_from[current];
+        }
+        positions.reverse();
+
+        Path {
+            positions,
+            cost: self.cost,
+            complete: self.complete,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub positions: Vec<Position>,
+    pub cost: u32,
+    pub complete: bool,
+}
+
+#[allow(dead_code)]
+pub trait Pathfinding {
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path>;
+
+    fn estimate_cost(&self, start: Position, goal: Position, map: &Map) -> Option<u32> {
+        self.find_path(start, goal, map)
+            .filter(|path| path.complete)
+            .map(|path| path.cost)
+    }
+
+    // The path to whichever goal is cheapest to reach. Searches once per
+    // goal unless the pathfinder can stop at the first goal it reaches.
+    fn find_path_to_any(&self, start: Position, goals: &[Position], map: &Map) -> Option<Path> {
+        goals
+            .iter()
+            .filter_map(|&goal| self.find_path(start, goal, map))
+            .min_by_key(|path| (!path.complete, path.cost))
+    }
+
+    // Whether every complete path is a cheapest one, which also makes every
+    // stretch of it a cheapest path between its ends.
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+// How a query treats cells currently held by other robots. The start and goal
+// cells are never affected.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OccupancyPolicy {
+    #[default]
+    Ignore,
+    Avoid,
+    Penalize(u32),
+}
+
+#[allow(dead_code)]
+pub fn occupied_cells(robots: &[Robot]) -> HashSet<Position> {
+    robots.iter().map(|robot| (robot.x, robot.y)).collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathfindingAlgorithm {
+    #[default]
+    AStar,
+    Bidirectional,
+    Hierarchical,
+}
+
+impl ValueEnum for PathfindingAlgorithm {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            PathfindingAlgorithm::AStar,
+            PathfindingAlgorithm::Bidirectional,
+            PathfindingAlgorithm::Hierarchical,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            PathfindingAlgorithm::AStar => PossibleValue::new("astar"),
+            PathfindingAlgorithm::Bidirectional => PossibleValue::new("bidirectional"),
+            PathfindingAlgorithm::Hierarchical => PossibleValue::new("hierarchical"),
+        })
+    }
+}
+
+impl fmt::Display for PathfindingAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+// Diagonal steps cost the same as straight ones (the cost of the cell being
+// entered) and may not cut corners past blocked cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MovementMode {
+    #[default]
+    Cardinal,
+    Diagonal,
+}
+
+impl ValueEnum for MovementMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[MovementMode::Cardinal, MovementMode::Diagonal]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            MovementMode::Cardinal => PossibleValue::new("cardinal"),
+            MovementMode::Diagonal => PossibleValue::new("diagonal"),
+        })
+    }
+}
+
+impl fmt::Display for MovementMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+impl MovementMode {
+    // Manhattan distance for 4-way moves and Chebyshev distance for 8-way
+    // moves, each the exact step count on open ground, so neither
+    // overestimates. Octile distance would only be needed if diagonals cost
+    // more than straight steps.
+    fn heuristic(self, from: Position, to: Position) -> u32 {
+        let (dx, dy) = (from.0.abs_diff(to.0), from.1.abs_diff(to.1));
+        match self {
+            MovementMode::Cardinal => (dx + dy) as u32,
+            MovementMode::Diagonal => dx.max(dy) as u32,
+        }
+    }
+
+    fn neighbors(self, map: &Map, (x, y): Position) -> Vec<Position> {
+        let mut cells: Vec<Position> = map.neighbors(x, y).collect();
+        if self == MovementMode::Diagonal {
+            for (dx, dy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+                let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                else {
+                    continue;
+                };
+                let open = |cx, cy| map.in_bounds(cx, cy) && map.movement_cost(cx, cy).is_some();
+                if open(nx, ny) && open(nx, y) && open(x, ny) {
+                    cells.push((nx, ny));
+                }
+            }
+        }
+        cells
+    }
+}
+
+pub fn from_config(config: &PathfindingConfig, map: &Map) -> Box<dyn Pathfinding> {
+    let inner: Box<dyn Pathfinding> = match config.algorithm {
+        PathfindingAlgorithm::AStar => {
+            let mut pathfinder = Pathfinder::new()
+                .with_heuristic_weight(config.heuristic_weight)
+                .with_movement(config.movement);
+            if let Some(budget) = config.expansion_budget {
+                pathfinder = pathfinder.with_expansion_budget(budget);
+            }
+            Box::new(pathfinder)
+        }
+        PathfindingAlgorithm::Bidirectional => Box::new(BidirectionalPathfinder::new()),
+        PathfindingAlgorithm::Hierarchical => Box::new(HierarchicalPathfinder::new(map)),
+    };
+    Box::new(CachedPathfinder::new(inner))
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Pathfinder {
+    expansion_budget: Option<usize>,
+    heuristic_weight: f64,
+    movement: MovementMode,
+}
+
+impl Default for Pathfinder {
+    fn default() -> Self {
+        Pathfinder {
+            expansion_budget: None,
+            heuristic_weight: 1.0,
+            movement: MovementMode::Cardinal,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Pathfinder {
+    pub fn new() -> Pathfinder {
+        Pathfinder::default()
+    }
+
+    pub fn with_expansion_budget(mut self, budget: usize) -> Pathfinder {
+        self.expansion_budget = Some(budget);
+        self
+    }
+
+    // Weights above 1.0 inflate the heuristic: far fewer expansions, paths
+    // at most `weight` times the optimal cost.
+    pub fn with_heuristic_weight(mut self, weight: f64) -> Pathfinder {
+        self.heuristic_weight = weight.max(1.0);
+        self
+    }
+
+    pub fn with_movement(mut self, movement: MovementMode) -> Pathfinder {
+        self.movement = movement;
+        self
+    }
+
+    fn weighted(&self, heuristic: u32) -> u32 {
+        (heuristic as f64 * self.heuristic_weight).round() as u32
+    }
+
+    pub fn find_path_around(
+        &self,
+        start: Position,
+        goal: Position,
+        map: &Map,
+        occupied: &HashSet<Position>,
+        policy: OccupancyPolicy,
+    ) -> Option<Path> {
+        self.search(start, &[goal], map, Some((occupied, policy)))
+            .map(|search| search.into_path(map))
+    }
+
+    fn search(
+        &self,
+        start: Position,
+        goals: &[Position],
+        map: &Map,
+        occupancy: Option<(&HashSet<Position>, OccupancyPolicy)>,
+    ) -> Option<Search> {
+        if !map.in_bounds(start.0, start.1) {
+            return None;
+        }
+        let goals: Vec<Position> = goals
+            .iter()
+            .copied()
+            .filter(|&(x, y)| map.in_bounds(x, y) && map.movement_cost(x, y).is_some())
+            .filter(|&goal| map.connected(start, goal))
+            .collect();
+        if goals.is_empty() {
+            return None;
+        }
+
+        let index = |(x, y): Position| y * map.width + x;
+        let start_index = index(start);
+        let goal_indices: Vec<usize> = goals.iter().map(|&goal| index(goal)).collect();
+        let nearest = |from: Position| {
+            goals
+                .iter()
+                .map(|&goal| self.movement.heuristic(from, goal))
+                .min()
+                .unwrap_or(0)
+        };
+
+        let mut best = vec![u32::MAX; map.width * map.height];
+        let mut came_from = vec![UNVISITED; map.width * map.height];
+        let mut open = BinaryHeap::new();
+
+        // Closest node to the goal seen so far, used as the end of a partial
+        // path when the expansion budget runs out.
+        let mut closest = (nearest(start), 0, start_index);
+        let mut expansions = 0;
+
+        best[start_index] = 0;
+        open.push(Reverse((self.weighted(nearest(start)), 0, start_index)));
+
+        while let Some(Reverse((_, cost, current))) = open.pop() {
+            if goal_indices.contains(&current) {
+                return Some(Search {
+                    cost,
+                    end: current,
+                    complete: true,
+                    came_from,
+                });
+            }
+            if cost > best[current] {
+                continue;
+            }
+
+            let position = (current % map.width, current / map.width);
+            closest = closest.min((nearest(position), cost, current));
+
+            expansions += 1;
+            if self
+                .expansion_budget
+                .is_some_and(|budget| expansions > budget)
+            {
+                let (_, cost, end) = closest;
+                log::warn!(
+                    "pathfinding budget of {} expansions exhausted from {:?} to {:?}, \
+                     returning partial path to {:?}",
+                    expansions - 1,
+                    start,
+                    goals,
+                    (end % map.width, end / map.width)
+                );
+                return Some(Search {
+                    cost,
+                    end,
+                    complete: false,
+                    came_from,
+                });
+            }
+
+            for next in self.movement.neighbors(map, position) {
+                let Some(mut step) = map.movement_cost(next.0, next.1) else {
+                    continue;
+                };
+                if let Some((occupied, policy)) = occupancy
+                    && !goals.contains(&next)
+                    && occupied.contains(&next)
+                {
+                    match policy {
+                        OccupancyPolicy::Ignore => {}
+                        OccupancyPolicy::Avoid => continue,
+                        OccupancyPolicy::Penalize(penalty) => step = step.saturating_add(penalty),
+                    }
+                }
+
+                let next_index = index(next);
+                let next_cost = cost.saturating_add(step);
+                if next_cost < best[next_index] {
+                    best[next_index] = next_cost;
+                    came_from[next_index] = current;
+                    open.push(Reverse((
+                        next_cost.saturating_add(self.weighted(nearest(next))),
+                        next_cost,
+                        next_index,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Pathfinding for Pathfinder {
+    fn find_path(&self, start: Position, goal: Position, map: &Map) -> Option<Path> {
+        self.search(start, &[goal], map, None)
+            .map(|search| search.into_path(map))
+    }
+
+    fn estimate_cost(&self, start: Position, goal: Position, map: &Map) -> Option<u32> {
+        self.search(start, &[goal], map, None)
+            .filter(|search| search.complete)
+            .map(|search| search.cost)
+    }
+
+    // One search that stops at whichever goal is cheapest to reach, using the
+    // distance to the nearest goal as the heuristic.
+    fn find_path_to_any(&self, start: Position, goals: &[Position], map: &Map) -> Option<Path> {
+        self.search(start, goals, map, None)
+            .map(|search| search.into_path(map))
+    }
+
+    fn is_exact(&self) -> bool {
+        self.heuristic_weight <= 1.0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::{Path, Position};
+    use crate::simulation::entities::Map;
+
+    // What walking `path` actually costs, or None if a step is not a move
+    // between neighboring open cells.
+    pub fn walked_cost(map: &Map, path: &Path) -> Option<u32> {
+        path.positions
+            .windows(2)
+            .map(|pair| {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                if x0.abs_diff(x1).max(y0.abs_diff(y1)) != 1 {
+                    return None;
+                }
+                map.movement_cost(x1, y1)
+            })
+            .sum()
+    }
+
+    pub fn open_cells(map: &Map) -> Vec<Position> {
+        (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.movement_cost(x, y).is_some())
+            .collect()
+    }
+
+    // A fixed spread of start/goal pairs over the open cells.
+    pub fn query_pairs(map: &Map, count: usize) -> Vec<(Position, Position)> {
+        let cells = open_cells(map);
+        (0..count)
+            .map(|i| {
+                (
+                    cells[(i * 7_919) % cells.len()],
+                    cells[(i * 104_729 + 17) % cells.len()],
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distance_field::DistanceField;
+    use super::testing::{query_pairs, walked_cost};
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn grid(rows: &[&str]) -> Map {
+        let terrain: Vec<Vec<u8>> = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|glyph| match glyph {
+                        '.' => 0,
+                        'n' => 1,
+                        '~' => 2,
+                        _ => 3,
+                    })
+                    .collect()
+            })
+            .collect();
+        let discovered = vec![vec![false; rows[0].len()]; rows.len()];
+        Map::from_parts(0, terrain, BTreeMap::new(), BTreeMap::new(), discovered).unwrap()
+    }
+
+    fn cost(map: &Map, movement: MovementMode, start: Position, goal: Position) -> u32 {
+        let path = Pathfinder::new()
+            .with_movement(movement)
+            .find_path(start, goal, map)
+            .unwrap();
+        assert!(path.complete);
+        assert_eq!(walked_cost(map, &path), Some(path.cost));
+        path.cost
+    }
+
+    #[test]
+    fn optimal_costs_on_known_grids() {
+        use MovementMode::{Cardinal, Diagonal};
+
+        let open = grid(&[".....", ".....", ".....", ".....", "....."]);
+        assert_eq!(cost(&open, Cardinal, (0, 0), (4, 4)), 8);
+        assert_eq!(cost(&open, Diagonal, (0, 0), (4, 4)), 4);
+
+        // Diagonals may not cut past the ends of the wall.
+        let wall = grid(&[".......", ".^^^^^.", "......."]);
+        assert_eq!(cost(&wall, Cardinal, (0, 1), (6, 1)), 8);
+        assert_eq!(cost(&wall, Diagonal, (0, 1), (6, 1)), 8);
+
+        let hill = grid(&[".....", "..n..", "....."]);
+        assert_eq!(cost(&hill, Cardinal, (0, 1), (4, 1)), 5);
+        assert_eq!(cost(&hill, Diagonal, (0, 1), (4, 1)), 4);
+
+        let canyon = grid(&[".~.", ".~.", ".~."]);
+        assert_eq!(cost(&canyon, Cardinal, (0, 0), (2, 2)), 6);
+        assert_eq!(cost(&canyon, Diagonal, (0, 0), (2, 2)), 4);
+    }
+
+    #[test]
+    fn heuristics_never_overestimate() {
+        for seed in [1, 8, 21] {
+            let map = Map::new(40, 24, seed).unwrap();
+            for movement in [MovementMode::Cardinal, MovementMode::Diagonal] {
+                for (start, goal) in query_pairs(&map, 40) {
+                    let field = DistanceField::with_movement(&map, goal, movement);
+                    let Some(optimal) = field.cost_at(start.0, start.1) else {
+                        continue;
+                    };
+                    assert!(
+                        movement.heuristic(start, goal) <= optimal,
+                        "seed {} {:?} {:?} -> {:?}",
+                        seed,
+                        movement,
+                        start,
+                        goal
+                    );
+                    assert_eq!(cost(&map, movement, start, goal), optimal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_of_several_goals_costs_the_cheapest_single_search() {
+        let map = Map::new(40, 24, 11).unwrap();
+        let cells = super::testing::open_cells(&map);
+        let astar = Pathfinder::new();
+        let pathfinders: [Box<dyn Pathfinding>; 3] = [
+            Box::new(Pathfinder::new()),
+            Box::new(bidirectional::BidirectionalPathfinder::new()),
+            Box::new(cache::CachedPathfinder::new(Box::new(Pathfinder::new()))),
+        ];
+
+        for (i, (start, _)) in query_pairs(&map, 20).into_iter().enumerate() {
+            let goals: Vec<Position> = (0..5)
+                .map(|k| cells[(i * 31 + k * 977) % cells.len()])
+                .collect();
+            let cheapest = goals
+                .iter()
+                .filter_map(|&goal| astar.estimate_cost(start, goal, &map))
+                .min();
+
+            for pathfinder in &pathfinders {
+                let path = pathfinder.find_path_to_any(start, &goals, &map);
+                assert_eq!(path.as_ref().map(|path| path.cost), cheapest);
+                if let Some(path) = path {
+                    assert_eq!(path.positions.first(), Some(&start));
+                    assert!(goals.contains(path.positions.last().unwrap()));
+                    assert_eq!(walked_cost(&map, &path), Some(path.cost));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_costs_stay_within_the_weight_of_optimal() {
+        for seed in [2, 13] {
+            let map = Map::new(48, 32, seed).unwrap();
+            for weight in [1.5, 3.0] {
+                let weighted = Pathfinder::new().with_heuristic_weight(weight);
+                for (start, goal) in query_pairs(&map, 60) {
+                    let Some(optimal) = Pathfinder::new().estimate_cost(start, goal, &map) else {
+                        continue;
+                    };
+                    let path = weighted.find_path(start, goal, &map).unwrap();
+                    assert_eq!(walked_cost(&map, &path), Some(path.cost));
+                    assert!(
+                        f64::from(path.cost) <= weight * f64::from(optimal),
+                        "seed {} weight {}: {} > {} x {}",
+                        seed,
+                        weight,
+                        path.cost,
+                        weight,
+                        optimal
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn occupied_cells_are_avoided_or_penalized() {
+        let map = grid(&[".....", "....."]);
+        let occupied = HashSet::from([(2, 0)]);
+        let around = |policy| {
+            Pathfinder::new()
+                .find_path_around((0, 0), (4, 0), &map, &occupied, policy)
+                .unwrap()
+        };
+
+        let ignored = around(OccupancyPolicy::Ignore);
+        assert_eq!(ignored.cost, 4);
+        assert!(ignored.positions.contains(&(2, 0)));
+
+        let avoided = around(OccupancyPolicy::Avoid);
+        assert_eq!(avoided.cost, 6);
+        assert!(!avoided.positions.contains(&(2, 0)));
+
+        // Waiting out a cheap penalty beats the two-step detour; a dear one
+        // does not.
+        let cheap = around(OccupancyPolicy::Penalize(1));
+        assert_eq!(cheap.cost, 5);
+        assert!(cheap.positions.contains(&(2, 0)));
+        let dear = around(OccupancyPolicy::Penalize(5));
+        assert_eq!(dear.positions, avoided.positions);
+        assert_eq!(around(OccupancyPolicy::Penalize(u32::MAX)).cost, 6);
+    }
+
+    #[test]
+    fn penalties_never_overflow_the_cost() {
+        let corridor = grid(&["....."]);
+        let occupied = HashSet::from([(2, 0)]);
+        let pathfinder = Pathfinder::new();
+        let find =
+            |policy| pathfinder.find_path_around((0, 0), (4, 0), &corridor, &occupied, policy);
+
+        assert_eq!(find(OccupancyPolicy::Avoid), None);
+        assert_eq!(find(OccupancyPolicy::Penalize(1_000)).unwrap().cost, 1_004);
+        // A penalty too large to add up saturates, which leaves the cell as
+        // unreachable as avoiding it.
+        assert_eq!(find(OccupancyPolicy::Penalize(u32::MAX)), None);
+    }
+
+    #[test]
+    fn an_exhausted_budget_returns_a_walkable_partial_path() {
+        let map = Map::new(40, 24, 6).unwrap();
+        let tight = Pathfinder::new().with_expansion_budget(5);
+        let mut partial = 0;
+        for (start, goal) in query_pairs(&map, 40) {
+            let Some(path) = tight.find_path(start, goal, &map) else {
+                continue;
+            };
+            assert_eq!(path.positions.first(), Some(&start));
+            assert_eq!(walked_cost(&map, &path), Some(path.cost));
+            if !path.complete {
+                partial += 1;
+                assert_ne!(path.positions.last(), Some(&goal));
+                assert_eq!(tight.estimate_cost(start, goal, &map), None);
+            }
+        }
+        assert!(partial > 0);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::simulation::entities::{Map, MapError, Robot, RobotId, RobotType, Station, TerrainType};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+const INITIAL_ENERGY: u32 = 100;
+const STATION_EDGE_MARGIN: usize = 3;
+const ROBOT_ROTATION: [RobotType; 3] = [
+    RobotType::Explorer,
+    RobotType::Harvester,
+    RobotType::Scientist,
+];
+
+#[derive(Debug, Clone)]
+pub struct RobotPlacement {
+    pub robot_type: RobotType,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl FromStr for RobotPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected <type>@<x>,<y>, got '{}'", s);
+
+        let (robot_type, position) = s.split_once('@').ok_or_else(usage)?;
+        let (x, y) = position.split_once(',').ok_or_else(usage)?;
+
+        Ok(RobotPlacement {
+            robot_type: robot_type.trim().parse()?,
+            x: x.trim().parse().map_err(|_| usage())?,
+            y: y.trim().parse().map_err(|_| usage())?,
+        })
+    }
+}
+
+pub fn place_station(map: &Map) -> Result<Station, MapError> {
+    let center = (map.width / 2, map.height / 2);
+
+    // Prefer the full edge margin, but relax it one cell at a time so small
+    // or crowded maps still get a station at the same spot for a given seed.
+    let (x, y) = (0..=STATION_EDGE_MARGIN)
+        .rev()
+        .find_map(|margin| {
+            breadth_first(map, center, |_, _| true)
+                .find(|&(x, y)| is_station_site(map, x, y, margin))
+        })
+        .ok_or(MapError::NoStationSite)?;
+
+    Ok(Station {
+        resources: HashMap::new(),
+        discoveries: 0,
+        x,
+        y,
+    })
+}
+
+pub fn spawn_robots(map: &Map, station: &Station, count: usize) -> Result<Vec<Robot>, MapError> {
+    let cells: Vec<(usize, usize)> = breadth_first(map, (station.x, station.y), |x, y| {
+        map.movement_cost(x, y).is_some()
+    })
+    .filter(|&(x, y)| (x, y) != (station.x, station.y))
+    .filter(|&(x, y)| map.terrain_at(x, y) == TerrainType::Plain)
+    .take(count)
+    .collect();
+
+    if cells.len() < count {
+        return Err(MapError::NoSpawnRoom {
+            requested: count,
+            available: cells.len(),
+        });
+    }
+
+    Ok(cells
+        .into_iter()
+        .enumerate()
+        .map(|(id, (x, y))| Robot {
+            id: RobotId(id),
+            robot_type: ROBOT_ROTATION[id % ROBOT_ROTATION.len()].clone(),
+            x,
+            y,
+            energy: INITIAL_ENERGY,
+            carrying: None,
+            movement_points: 0,
+            movement_cost: 0,
+        })
+        .collect())
+}
+
+pub fn place_robots(
+    map: &Map,
+    station: &Station,
+    placements: &[RobotPlacement],
+) -> Result<Vec<Robot>, MapError> {
+    let mut occupied = HashSet::from([(station.x, station.y)]);
+    let mut robots = Vec::with_capacity(placements.len());
+
+    for (id, placement) in placements.iter().enumerate() {
+        let (x, y) = (placement.x, placement.y);
+        let invalid = |reason| MapError::InvalidRobotPlacement { x, y, reason };
+
+        if !map.in_bounds(x, y) {
+            return Err(invalid("outside the map"));
+        }
+        if map.is_no_go(x, y) {
+            return Err(invalid("inside a no-go zone"));
+        }
+        if !map.terrain_at(x, y).is_traversable() {
+            return Err(invalid("not traversable"));
+        }
+        if !occupied.insert((x, y)) {
+            return Err(invalid("already occupied"));
+        }
+
+        robots.push(Robot {
+            id: RobotId(id),
+            robot_type: placement.robot_type.clone(),
+            x,
+            y,
+            energy: INITIAL_ENERGY,
+            carrying: None,
+            movement_points: 0,
+            movement_cost: 0,
+        });
+    }
+
+    Ok(robots)
+}
+
+fn is_station_site(map: &Map, x: usize, y: usize, margin: usize) -> bool {
+    let away_from_edge =
+        x >= margin && y >= margin && x + margin < map.width && y + margin < map.height;
+
+    away_from_edge
+        && map.terrain_at(x, y) == TerrainType::Plain
+        && !map.is_no_go(x, y)
+        && !map.resources.contains_key(&(x, y))
+}
+
+fn breadth_first<'a, P>(
+    map: &'a Map,
+    start: (usize, usize),
+    passable: P,
+) -> impl Iterator<Item = (usize, usize)> + 'a
+where
+    P: Fn(usize, usize) -> bool + 'a,
+{
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+
+    std::iter::from_fn(move || {
+        let (x, y) = queue.pop_front()?;
+        for (nx, ny) in map.neighbors(x, y) {
+            if passable(nx, ny) && visited.insert((nx, ny)) {
+                queue.push_back((nx, ny));
+            }
+        }
+        Some((x, y))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::view::Rect;
+    use std::collections::BTreeMap;
+
+    const SEEDS: std::ops::Range<u64> = 0..200;
+
+    fn full_margin_site_exists(map: &Map) -> bool {
+        (0..map.height)
+            .any(|y| (0..map.width).any(|x| is_station_site(map, x, y, STATION_EDGE_MARGIN)))
+    }
+
+    #[test]
+    fn station_sits_on_free_plain_away_from_the_edge() {
+        for seed in SEEDS {
+            let map = Map::new(48, 24, seed).unwrap();
+            let station = place_station(&map).unwrap();
+            let (x, y) = (station.x, station.y);
+
+            assert_eq!(map.terrain_at(x, y), TerrainType::Plain, "seed {}", seed);
+            assert!(!map.resources.contains_key(&(x, y)), "seed {}", seed);
+            if full_margin_site_exists(&map) {
+                assert!(
+                    is_station_site(&map, x, y, STATION_EDGE_MARGIN),
+                    "seed {} placed the station at ({}, {}) inside the margin",
+                    seed,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn station_falls_back_to_a_smaller_margin_on_tiny_maps() {
+        for seed in SEEDS {
+            let map = Map::new(5, 5, seed).unwrap();
+            let Ok(station) = place_station(&map) else {
+                continue;
+            };
+            assert_eq!(map.terrain_at(station.x, station.y), TerrainType::Plain);
+            assert!(!map.resources.contains_key(&(station.x, station.y)));
+        }
+    }
+
+    #[test]
+    fn station_placement_is_deterministic() {
+        for seed in SEEDS {
+            let map = Map::new(48, 24, seed).unwrap();
+            let (a, b) = (place_station(&map).unwrap(), place_station(&map).unwrap());
+            assert_eq!((a.x, a.y), (b.x, b.y), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn each_invalid_placement_is_rejected_with_its_reason() {
+        let mut terrain = vec![vec![TerrainType::Plain as u8; 5]; 5];
+        terrain[4][4] = TerrainType::Mountain as u8;
+        let mut map = Map::from_parts(
+            0,
+            terrain,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; 5]; 5],
+        )
+        .unwrap();
+        map.add_no_go_zone(Rect {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        })
+        .unwrap();
+        let station = Station {
+            resources: HashMap::new(),
+            discoveries: 0,
+            x: 2,
+            y: 2,
+        };
+        let at = |x, y| RobotPlacement {
+            robot_type: RobotType::Explorer,
+            x,
+            y,
+        };
+        let rejection =
+            |placements: &[RobotPlacement]| match place_robots(&map, &station, placements) {
+                Err(MapError::InvalidRobotPlacement { reason, .. }) => reason,
+                other => panic!(
+                    "expected a rejected placement, got {:?}",
+                    other.map(|robots| robots.len())
+                ),
+            };
+
+        assert_eq!(rejection(&[at(5, 0)]), "outside the map");
+        assert_eq!(rejection(&[at(0, 0)]), "inside a no-go zone");
+        assert_eq!(rejection(&[at(4, 4)]), "not traversable");
+        assert_eq!(rejection(&[at(2, 2)]), "already occupied");
+        assert_eq!(rejection(&[at(1, 1), at(1, 1)]), "already occupied");
+        assert_eq!(
+            place_robots(&map, &station, &[at(1, 1), at(3, 3)])
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::simulation::entities::{Map, ResourceType, TerrainType};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceStats {
+    pub deposits: usize,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MapStats {
+    pub cells: usize,
+    pub terrain: HashMap<TerrainType, usize>,
+    pub resources: HashMap<ResourceType, ResourceStats>,
+    pub discovered: usize,
+}
+
+impl MapStats {
+    pub fn terrain_count(&self, terrain: TerrainType) -> usize {
+        self.terrain.get(&terrain).copied().unwrap_or(0)
+    }
+
+    pub fn resource(&self, resource_type: &ResourceType) -> ResourceStats {
+        self.resources
+            .get(resource_type)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn deposits(&self) -> usize {
+        self.resources.values().map(|r| r.deposits).sum()
+    }
+}
+
+// Rough heap footprint, in bytes, of each named part of the simulation. It
+// counts payloads and container headers but not allocator overhead.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStats {
+    pub entries: Vec<(&'static str, usize)>,
+}
+
+impl MemoryStats {
+    pub fn add(&mut self, name: &'static str, bytes: usize) {
+        self.entries.push((name, bytes));
+    }
+
+    pub fn total(&self) -> usize {
+        self.entries.iter().map(|(_, bytes)| bytes).sum()
+    }
+}
+
+impl fmt::Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, bytes) in &self.entries {
+            writeln!(f, "    {:<16} {}", name, format_bytes(*bytes))?;
+        }
+        writeln!(f, "    {:<16} {}", "total", format_bytes(self.total()))
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn grid_bytes<T>(width: usize, height: usize) -> usize {
+    height * (size_of::<Vec<T>>() + width * size_of::<T>())
+}
+
+const SCORE_SECTOR_SIZE: usize = 8;
+
+impl Map {
+    // Rough 0.0..=1.0 rating of how interesting a world is to play, averaging
+    // terrain variety, how varied and cheap to reach the deposits are, and how
+    // evenly they cover the map.
+    pub fn interest_score(&self) -> f64 {
+        let stats = self.stats();
+
+        let cells = stats.cells as f64;
+        let entropy: f64 = stats
+            .terrain
+            .values()
+            .map(|&count| count as f64 / cells)
+            .filter(|&share| share > 0.0)
+            .map(|share| -share * share.ln())
+            .sum();
+        let terrain_balance = entropy / 4f64.ln();
+
+        let present = ResourceType::value_variants()
+            .iter()
+            .filter(|resource_type| stats.resource(resource_type).deposits > 0)
+            .count();
+        let variety = present as f64 / ResourceType::value_variants().len() as f64;
+        let accessibility = if self.resources.is_empty() {
+            0.0
+        } else {
+            self.resources
+                .keys()
+                .filter_map(|&(x, y)| self.movement_cost(x, y))
+                .map(|cost| 1.0 / cost as f64)
+                .sum::<f64>()
+                / self.resources.len() as f64
+        };
+        let resources = (variety + accessibility) / 2.0;
+
+        let sectors_x = self.width.div_ceil(SCORE_SECTOR_SIZE);
+        let sectors_y = self.height.div_ceil(SCORE_SECTOR_SIZE);
+        let mut covered = vec![false; sectors_x * sectors_y];
+        for &(x, y) in self.resources.keys() {
+            covered[(y / SCORE_SECTOR_SIZE) * sectors_x + x / SCORE_SECTOR_SIZE] = true;
+        }
+        let spread = covered.iter().filter(|&&c| c).count() as f64 / covered.len() as f64;
+
+        (terrain_balance + resources + spread) / 3.0
+    }
+
+    pub fn memory_usage(&self) -> MemoryStats {
+        let mut usage = MemoryStats::default();
+        usage.add("terrain", grid_bytes::<u8>(self.width, self.height));
+        usage.add("discovered", grid_bytes::<bool>(self.width, self.height));
+        usage.add(
+            "regions",
+            grid_bytes::<Option<usize>>(self.width, self.height),
+        );
+        usage.add(
+            "resource index",
+            (self.resources.len() + self.deep_resources.len())
+                * size_of::<((usize, usize), (ResourceType, u32))>(),
+        );
+        usage
+    }
+
+    pub fn stats(&self) -> MapStats {
+        let mut stats = MapStats {
+            cells: self.width * self.height,
+            ..MapStats::default()
+        };
+
+        for row in &self.terrain {
+            for &cell in row {
+                *stats.terrain.entry(TerrainType::from_u8(cell)).or_default() += 1;
+            }
+        }
+
+        for (resource_type, amount) in self.resources.values() {
+            let entry = stats.resources.entry(resource_type.clone()).or_default();
+            entry.deposits += 1;
+            entry.total += u64::from(*amount);
+        }
+
+        stats.discovered = self.discovered.iter().flatten().filter(|&&d| d).count();
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn interest_scores_stay_within_zero_and_one() {
+        for seed in 0..40 {
+            for (width, height) in [(8, 8), (30, 12), (64, 32)] {
+                let score = Map::new(width, height, seed).unwrap().interest_score();
+                assert!((0.0..=1.0).contains(&score), "seed {}: {}", seed, score);
+            }
+        }
+    }
+
+    #[test]
+    fn a_flat_empty_world_scores_zero() {
+        let map = Map::from_parts(
+            0,
+            vec![vec![0; 12]; 6],
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![vec![false; 12]; 6],
+        )
+        .unwrap();
+        assert_eq!(map.interest_score(), 0.0);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::simulation::entities::{Map, Robot, RobotId};
+use crate::simulation::pathfinding::{Path, Pathfinder, Position};
+use crate::simulation::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    Moved { robot: RobotId, to: Position },
+    Waited { robot: RobotId },
+    Arrived { robot: RobotId },
+}
+
+// Drives a small seeded world for scenario tests: every robot is sent along
+// its planned route, one tick at a time in id order, and whatever happened
+// on each tick is kept so tests can assert over the whole run.
+pub struct TestHarness {
+    world: World,
+    routes: Vec<(RobotId, Path)>,
+    events: Vec<(u64, Event)>,
+}
+
+impl TestHarness {
+    pub fn new(seed: u64) -> TestHarness {
+        let map = Map::new(32, 16, seed).unwrap();
+        TestHarness::with_world(World::new(map, 4, &[]).unwrap())
+    }
+
+    pub fn with_world(world: World) -> TestHarness {
+        let routes = world.plan_routes(&Pathfinder::new());
+        TestHarness {
+            world,
+            routes,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn run(&mut self, ticks: u64) -> &mut TestHarness {
+        for _ in 0..ticks {
+            self.step();
+        }
+        self
+    }
+
+    pub fn step(&mut self) {
+        let tick = self.world.step();
+        for (id, path) in &self.routes {
+            let robot = *id;
+            if self.arrived(robot) {
+                continue;
+            }
+            let event = match self.world.advance_robot(robot, &path.positions) {
+                0 => Event::Waited { robot },
+                _ => {
+                    let to = position(self.world.robots(), robot);
+                    Event::Moved { robot, to }
+                }
+            };
+            self.events.push((tick, event));
+            if path.positions.last() == Some(&position(self.world.robots(), robot)) {
+                self.events.push((tick, Event::Arrived { robot }));
+            }
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn routes(&self) -> &[(RobotId, Path)] {
+        &self.routes
+    }
+
+    pub fn events(&self) -> &[(u64, Event)] {
+        &self.events
+    }
+
+    pub fn robot(&self, id: RobotId) -> &Robot {
+        self.world
+            .robots()
+            .iter()
+            .find(|robot| robot.id == id)
+            .expect("no robot with that id")
+    }
+
+    pub fn arrived(&self, id: RobotId) -> bool {
+        self.events
+            .iter()
+            .any(|&(_, event)| event == Event::Arrived { robot: id })
+    }
+}
+
+fn position(robots: &[Robot], id: RobotId) -> Position {
+    robots
+        .iter()
+        .find(|robot| robot.id == id)
+        .map(|robot| (robot.x, robot.y))
+        .expect("no robot with that id")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn robots_only_move_along_their_routes_and_never_share_a_cell() {
+        for seed in 0..20 {
+            let mut harness = TestHarness::new(seed);
+            for _ in 0..60 {
+                harness.step();
+
+                let station = harness.world().station();
+                let mut seen = HashSet::new();
+                for robot in harness.world().robots() {
+                    let cell = (robot.x, robot.y);
+                    assert!(
+                        cell == (station.x, station.y) || seen.insert(cell),
+                        "seed {}: two robots on {:?}",
+                        seed,
+                        cell
+                    );
+                }
+            }
+
+            for &(_, event) in harness.events() {
+                if let Event::Moved { robot, to } = event {
+                    let (_, path) = harness
+                        .routes()
+                        .iter()
+                        .find(|(id, _)| *id == robot)
+                        .unwrap();
+                    assert!(path.positions.contains(&to), "seed {}", seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arrivals_end_at_the_route_and_stop_the_robot() {
+        let mut arrivals = 0;
+        for seed in 0..20 {
+            let mut harness = TestHarness::new(seed);
+            harness.run(200);
+
+            for (id, path) in harness.routes() {
+                if !harness.arrived(*id) {
+                    continue;
+                }
+                arrivals += 1;
+                let robot = harness.robot(*id);
+                assert_eq!(Some(&(robot.x, robot.y)), path.positions.last());
+
+                let arrived_at = harness
+                    .events()
+                    .iter()
+                    .find(|&&(_, event)| event == Event::Arrived { robot: *id })
+                    .map(|&(tick, _)| tick)
+                    .unwrap();
+                assert!(harness.events().iter().all(|&(tick, event)| {
+                    tick <= arrived_at
+                        || !matches!(
+                            event,
+                            Event::Moved { robot, .. } | Event::Waited { robot } if robot == *id
+                        )
+                }));
+            }
+        }
+        assert!(arrivals > 0);
+    }
+
+    #[test]
+    fn the_same_seed_replays_the_same_events() {
+        let mut first = TestHarness::new(5);
+        let mut second = TestHarness::new(5);
+        first.run(100);
+        second.run(100);
+        assert_eq!(first.events(), second.events());
+    }
+}
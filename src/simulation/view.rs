@@ -0,0 +1,237 @@
+use crate::simulation::entities::{Map, ResourceType, Robot, TerrainType};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    // A `width` x `height` window centered on `center` as far as the map
+    // allows, shifted back inside when it would cross an edge.
+    pub fn centered(map: &Map, center: (usize, usize), width: usize, height: usize) -> Rect {
+        let width = width.min(map.width);
+        let height = height.min(map.height);
+        Rect {
+            x: center.0.saturating_sub(width / 2).min(map.width - width),
+            y: center.1.saturating_sub(height / 2).min(map.height - height),
+            width,
+            height,
+        }
+    }
+
+    // Offsets from the corner rather than `x + width`, which can overflow for
+    // a rect parsed from user input.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x.checked_sub(self.x).is_some_and(|dx| dx < self.width)
+            && y.checked_sub(self.y).is_some_and(|dy| dy < self.height)
+    }
+
+    pub fn fits_within(&self, width: usize, height: usize) -> bool {
+        self.x
+            .checked_add(self.width)
+            .is_some_and(|right| right <= width)
+            && self
+                .y
+                .checked_add(self.height)
+                .is_some_and(|bottom| bottom <= height)
+    }
+}
+
+impl FromStr for Rect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("expected <x>,<y>,<width>x<height>, got '{}'", s);
+        let number = |value: &str| value.trim().parse().map_err(|_| usage());
+
+        let (x, rest) = s.split_once(',').ok_or_else(usage)?;
+        let (y, size) = rest.split_once(',').ok_or_else(usage)?;
+        let (width, height) = size.split_once('x').ok_or_else(usage)?;
+
+        let rect = Rect {
+            x: number(x)?,
+            y: number(y)?,
+            width: number(width)?,
+            height: number(height)?,
+        };
+        if rect.width == 0 || rect.height == 0 {
+            return Err(format!("'{}' has an empty area", s));
+        }
+        if !rect.fits_within(usize::MAX, usize::MAX) {
+            return Err(format!("'{}' reaches past the largest coordinate", s));
+        }
+        Ok(rect)
+    }
+}
+
+// The part of a map that falls inside a rectangle. Resources are collected up
+// front from a range query so consumers never walk off-screen deposits.
+#[allow(dead_code)]
+pub struct MapView<'a> {
+    pub rect: Rect,
+    map: &'a Map,
+    resources: Vec<((usize, usize), ResourceType, u32)>,
+}
+
+#[allow(dead_code)]
+impl MapView<'_> {
+    pub fn terrain_at(&self, x: usize, y: usize) -> Option<TerrainType> {
+        self.rect.contains(x, y).then(|| self.map.terrain_at(x, y))
+    }
+
+    pub fn is_no_go(&self, x: usize, y: usize) -> bool {
+        self.rect.contains(x, y) && self.map.is_no_go(x, y)
+    }
+
+    pub fn resource_at(&self, x: usize, y: usize) -> Option<&ResourceType> {
+        if !self.rect.contains(x, y) {
+            return None;
+        }
+        self.map
+            .resources
+            .get(&(x, y))
+            .map(|(resource_type, _)| resource_type)
+    }
+
+    pub fn resources(&self) -> &[((usize, usize), ResourceType, u32)] {
+        &self.resources
+    }
+
+    pub fn robots<'r>(&self, robots: &'r [Robot]) -> impl Iterator<Item = &'r Robot> + use<'r, '_> {
+        robots
+            .iter()
+            .filter(|robot| self.rect.contains(robot.x, robot.y))
+    }
+}
+
+impl Map {
+    pub fn view(&self, rect: Rect) -> MapView<'_> {
+        let rect = Rect {
+            x: rect.x.min(self.width),
+            y: rect.y.min(self.height),
+            width: rect.width.min(self.width.saturating_sub(rect.x)),
+            height: rect.height.min(self.height.saturating_sub(rect.y)),
+        };
+
+        // Resources are keyed by (x, y), so a key range covers the columns and
+        // only rows need filtering.
+        let resources = self
+            .resources
+            .range((rect.x, 0)..(rect.x + rect.width, 0))
+            .filter(|((x, y), _)| rect.contains(*x, *y))
+            .map(|(&cell, (resource_type, amount))| (cell, resource_type.clone(), *amount))
+            .collect();
+
+        MapView {
+            rect,
+            map: self,
+            resources,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::entities::{RobotId, RobotType};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn contains_does_not_overflow_at_the_edge_of_usize() {
+        let rect = Rect {
+            x: usize::MAX - 2,
+            y: 0,
+            width: 2,
+            height: 5,
+        };
+        assert!(rect.contains(usize::MAX - 1, 4));
+        assert!(!rect.contains(usize::MAX, 0));
+        assert!(!rect.contains(0, 0));
+    }
+
+    #[test]
+    fn parsing_rejects_empty_and_overflowing_rects() {
+        assert_eq!(
+            "2,3,4x5".parse::<Rect>(),
+            Ok(Rect {
+                x: 2,
+                y: 3,
+                width: 4,
+                height: 5
+            })
+        );
+        assert!("2,3,0x5".parse::<Rect>().is_err());
+        assert!("2,3,4x0".parse::<Rect>().is_err());
+        assert!("18446744073709551615,0,5x5".parse::<Rect>().is_err());
+        assert!("0,18446744073709551615,5x5".parse::<Rect>().is_err());
+    }
+
+    fn robot_at(id: usize, x: usize, y: usize) -> Robot {
+        Robot {
+            id: RobotId(id),
+            robot_type: RobotType::Explorer,
+            x,
+            y,
+            energy: 100,
+            carrying: None,
+            movement_points: 0,
+            movement_cost: 0,
+        }
+    }
+
+    #[test]
+    fn views_hold_only_what_lies_inside_the_clipped_rect() {
+        let resources = BTreeMap::from([
+            ((1, 1), (ResourceType::Energy, 10)),
+            ((4, 1), (ResourceType::Mineral, 20)),
+            ((1, 3), (ResourceType::Mineral, 30)),
+            ((4, 3), (ResourceType::ScientificInterest, 40)),
+            ((5, 2), (ResourceType::Energy, 50)),
+        ]);
+        let map = Map::from_parts(
+            0,
+            vec![vec![TerrainType::Plain as u8; 6]; 4],
+            resources,
+            BTreeMap::new(),
+            vec![vec![false; 6]; 4],
+        )
+        .unwrap();
+        let robots = [robot_at(0, 0, 0), robot_at(1, 3, 2), robot_at(2, 5, 3)];
+
+        let view = map.view(Rect {
+            x: 3,
+            y: 2,
+            width: 10,
+            height: 10,
+        });
+        assert_eq!(
+            view.rect,
+            Rect {
+                x: 3,
+                y: 2,
+                width: 3,
+                height: 2
+            }
+        );
+        let cells: Vec<_> = view.resources().iter().map(|&(cell, _, _)| cell).collect();
+        assert_eq!(cells, [(4, 3), (5, 2)]);
+        assert_eq!(view.resource_at(4, 1), None);
+        assert_eq!(view.terrain_at(2, 2), None);
+        let ids: Vec<_> = view.robots(&robots).map(|robot| robot.id).collect();
+        assert_eq!(ids, [RobotId(1), RobotId(2)]);
+
+        let outside = map.view(Rect {
+            x: 8,
+            y: 0,
+            width: 2,
+            height: 2,
+        });
+        assert_eq!(outside.rect.width, 0);
+        assert!(outside.resources().is_empty());
+        assert_eq!(outside.robots(&robots).count(), 0);
+    }
+}
@@ -0,0 +1,74 @@
+use super::theme::Theme;
+use super::{MAX_RENDER_HEIGHT, MAX_RENDER_WIDTH, render_map};
+use crate::simulation::entities::{Map, Robot, Station};
+use crate::simulation::view::Rect;
+use std::io::{self, Write};
+
+pub struct Frame<'a> {
+    pub map: &'a Map,
+    pub station: &'a Station,
+    pub robots: &'a [Robot],
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontendCommand {
+    Quit,
+}
+
+// Anything that can show the simulation to a user. The engine only hands out
+// frames and reads back commands, so new frontends never touch it.
+#[allow(dead_code)]
+pub trait Frontend {
+    fn init(&mut self, map: &Map) -> io::Result<()>;
+    fn render(&mut self, frame: &Frame) -> io::Result<()>;
+    fn handle_input(&mut self) -> io::Result<Option<FrontendCommand>>;
+}
+
+pub struct PlainTextFrontend {
+    theme: Theme,
+}
+
+impl PlainTextFrontend {
+    pub fn new(theme: Theme) -> PlainTextFrontend {
+        PlainTextFrontend { theme }
+    }
+}
+
+impl Frontend for PlainTextFrontend {
+    fn init(&mut self, _map: &Map) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &Frame) -> io::Result<()> {
+        // Larger maps would wrap and be unreadable in a terminal, so only the
+        // window around the station is drawn.
+        let rect = Rect::centered(
+            frame.map,
+            (frame.station.x, frame.station.y),
+            MAX_RENDER_WIDTH,
+            MAX_RENDER_HEIGHT,
+        );
+        let view = frame.map.view(rect);
+
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout)?;
+        if (rect.width, rect.height) != (frame.map.width, frame.map.height) {
+            writeln!(
+                stdout,
+                "Showing {}x{} around the station from ({}, {})",
+                rect.width, rect.height, rect.x, rect.y
+            )?;
+        }
+        write!(
+            stdout,
+            "{}",
+            render_map(&view, Some(frame.station), frame.robots, &self.theme)
+        )?;
+        stdout.flush()
+    }
+
+    fn handle_input(&mut self) -> io::Result<Option<FrontendCommand>> {
+        Ok(None)
+    }
+}
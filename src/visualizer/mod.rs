@@ -0,0 +1,69 @@
+pub mod frontend;
+pub mod theme;
+
+use crate::simulation::entities::{ResourceType, Robot, RobotType, Station, TerrainType};
+use crate::simulation::view::MapView;
+use console::StyledObject;
+use theme::Theme;
+
+pub const MAX_RENDER_WIDTH: usize = 160;
+pub const MAX_RENDER_HEIGHT: usize = 80;
+
+pub fn render_map(
+    view: &MapView,
+    station: Option<&Station>,
+    robots: &[Robot],
+    theme: &Theme,
+) -> String {
+    let rect = view.rect;
+    let mut out = String::with_capacity(rect.width * rect.height + rect.height);
+    let robots: Vec<&Robot> = view.robots(robots).collect();
+
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            let glyph = if station.is_some_and(|station| (x, y) == (station.x, station.y)) {
+                theme.station.apply_to('#')
+            } else if let Some(robot) = robots.iter().find(|r| (r.x, r.y) == (x, y)) {
+                robot_glyph(&robot.robot_type, theme)
+            } else if view.is_no_go(x, y) {
+                theme.no_go.apply_to('x')
+            } else if let Some(resource_type) = view.resource_at(x, y) {
+                resource_glyph(resource_type, theme)
+            } else if let Some(terrain) = view.terrain_at(x, y) {
+                terrain_glyph(terrain, theme)
+            } else {
+                continue;
+            };
+            out.push_str(&glyph.to_string());
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn robot_glyph(robot_type: &RobotType, theme: &Theme) -> StyledObject<char> {
+    let glyph = match robot_type {
+        RobotType::Explorer => 'E',
+        RobotType::Harvester => 'H',
+        RobotType::Scientist => 'S',
+    };
+    theme.robot.apply_to(glyph)
+}
+
+fn resource_glyph(resource_type: &ResourceType, theme: &Theme) -> StyledObject<char> {
+    match resource_type {
+        ResourceType::Energy => theme.energy.apply_to('+'),
+        ResourceType::Mineral => theme.mineral.apply_to('*'),
+        ResourceType::ScientificInterest => theme.science.apply_to('?'),
+    }
+}
+
+fn terrain_glyph(terrain: TerrainType, theme: &Theme) -> StyledObject<char> {
+    match terrain {
+        TerrainType::Plain => theme.plain.apply_to('.'),
+        TerrainType::Hill => theme.hill.apply_to('n'),
+        TerrainType::Canyon => theme.canyon.apply_to('~'),
+        TerrainType::Mountain => theme.mountain.apply_to('^'),
+    }
+}
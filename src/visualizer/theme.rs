@@ -0,0 +1,84 @@
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
+use console::Style;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl ValueEnum for Palette {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Palette::Default, Palette::Deuteranopia, Palette::Protanopia]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Palette::Default => PossibleValue::new("default"),
+            Palette::Deuteranopia => PossibleValue::new("deuteranopia"),
+            Palette::Protanopia => PossibleValue::new("protanopia"),
+        })
+    }
+}
+
+pub struct Theme {
+    pub station: Style,
+    pub robot: Style,
+    pub energy: Style,
+    pub mineral: Style,
+    pub science: Style,
+    pub plain: Style,
+    pub hill: Style,
+    pub canyon: Style,
+    pub mountain: Style,
+    pub no_go: Style,
+}
+
+impl Palette {
+    // The colorblind palettes keep terrain on a neutral grey ramp and give
+    // resources saturated hues that differ mainly in blue/yellow and in
+    // lightness, which stay distinguishable without red/green perception.
+    pub fn theme(self) -> Theme {
+        match self {
+            Palette::Default => Theme {
+                station: Style::new().magenta().bold(),
+                robot: Style::new().white().bold().on_blue(),
+                energy: Style::new().yellow().bold(),
+                mineral: Style::new().cyan().bold(),
+                science: Style::new().green().bold(),
+                plain: Style::new().dim(),
+                hill: Style::new().color256(136),
+                canyon: Style::new().color256(94),
+                mountain: Style::new().white(),
+                no_go: Style::new().red().on_black(),
+            },
+            Palette::Deuteranopia => Theme {
+                station: Style::new().black().bold().on_color256(220),
+                robot: Style::new().black().bold().on_white(),
+                energy: Style::new().color256(220).bold(),
+                mineral: Style::new().color256(33).bold(),
+                science: Style::new().color256(153).bold(),
+                plain: Style::new().color256(240),
+                hill: Style::new().color256(245),
+                canyon: Style::new().color256(237),
+                mountain: Style::new().color256(252),
+                no_go: Style::new().color256(252).on_color256(235),
+            },
+            Palette::Protanopia => Theme {
+                station: Style::new().black().bold().on_color256(226),
+                robot: Style::new().black().bold().on_white(),
+                energy: Style::new().color256(226).bold(),
+                mineral: Style::new().color256(27).bold(),
+                science: Style::new().color256(117).bold(),
+                plain: Style::new().color256(240),
+                hill: Style::new().color256(245),
+                canyon: Style::new().color256(237),
+                mountain: Style::new().color256(252),
+                no_go: Style::new().color256(252).on_color256(235),
+            },
+        }
+    }
+}